@@ -3,18 +3,41 @@
 #![cfg_attr(external_doc, warn(missing_docs))]
 #![forbid(unsafe_code)]
 
+mod blame;              pub         use blame::{BlameHunk, BlameOptions}; pub(crate) use blame::BlameHunks;
 pub mod blob;           pub(crate)  use blob::Blob;
 mod branch;             pub         use branch::*;
+mod cat_file_batch;     pub(crate)  use cat_file_batch::*;
 mod cat_file_reader;    pub(crate)  use cat_file_reader::*;
 pub mod commit;         pub         use commit::Commit;
+mod discover;           pub         use discover::DiscoverOptions; pub(crate) use discover::{canonicalize, discover, validate_git_dir};
 mod file_type;          pub         use file_type::*;
 pub mod generic;        pub         use generic::HashParseError; pub(crate) use generic::HashTempStr;
+mod git_error;          pub         use git_error::*;
+mod git_options;        pub         use git_options::GitOptions; pub(crate) use git_options::parse_git_version;
+mod git_process;        pub(crate)  use git_process::*;
+mod hash_prefix;        pub         use hash_prefix::*;
+mod index;              pub         use index::*;
+mod merge_base;
 mod name;               pub         use name::*;
+mod object_headers;     pub(crate)  use object_headers::*;
+mod object_info;        pub         use object_info::*;
+mod packed_refs;        pub(crate)  use packed_refs::*;
+mod path_error;         pub         use path_error::*;
+mod reference;          pub         use reference::*;
 mod repository;         pub         use repository::*;
 mod repository_cache;   pub         use repository_cache::*;
+mod rev_parse;          pub         use rev_parse::*;
+mod rev_walk;           pub         use rev_walk::*;
 mod shared_hash_map;    pub(crate)  use shared_hash_map::*;
+mod signature;          pub         use signature::*;
+mod status;             pub         use status::{Status, StatusBranch, StatusChange, StatusCode, StatusEntry, StatusOptions, StatusUnmerged, SubmoduleStatus}; pub(crate) use status::parse_status;
+mod symbolic_ref;       pub         use symbolic_ref::*;
+pub mod tag;            pub         use tag::Tag;
 pub mod tree;           pub         use tree::Tree;
+mod tree_diff;          pub         use tree_diff::{TreeChange, TreeDiffOptions};
+mod tree_walk;          pub         use tree_walk::*;
 pub mod unknown;
+mod worktree;           pub         use worktree::Worktree; pub(crate) use worktree::{common_dir, is_per_worktree_ref, list_worktrees, resolve_dot_git, PER_WORKTREE_REF_DIRS};
 
 
 
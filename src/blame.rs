@@ -0,0 +1,295 @@
+use crate::*;
+
+use std::collections::*;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::ChildStdout;
+
+
+
+/// Options for [Repository::blame]
+#[derive(Clone, Debug, Default)]
+pub struct BlameOptions {
+    /// Only blame these inclusive, 1-based (start, end) line ranges (`git blame -L start,end`).  Empty blames the whole file.
+    pub lines:              Vec<(usize, usize)>,
+
+    /// Ignore the commits listed in this file, like `.git-blame-ignore-revs` (`git blame --ignore-revs-file`)
+    pub ignore_revs_file:   Option<PathBuf>,
+
+    /// Ignore these commits (`git blame --ignore-rev`)
+    pub ignore_revs:        Vec<commit::Hash>,
+}
+
+/// A run of consecutive lines attributed to the same [Commit], as reported by [Repository::blame]
+#[derive(Clone, Debug)]
+pub struct BlameHunk {
+    /// The [Commit] that last changed these lines
+    pub commit:         commit::Hash,
+
+    /// The 1-based line number of the first line in [commit](Self::commit)'s version of the file
+    pub original_line:  usize,
+
+    /// The 1-based line number of the first line in the blamed version of the file
+    pub final_line:     usize,
+
+    /// How many lines this hunk covers
+    pub lines:          usize,
+
+    /// The author of [commit](Self::commit)
+    pub author:         Signature,
+
+    /// The committer of [commit](Self::commit)
+    pub committer:      Signature,
+
+    /// The first line of [commit](Self::commit)'s message
+    pub summary:        Name,
+
+    /// The path of the file in [commit](Self::commit) (differs from the blamed path if the file was since renamed)
+    pub original_path:  Name,
+
+    /// The parent [Commit] and path these lines were blamed past, if any
+    pub previous:       Option<(commit::Hash, Name)>,
+
+    /// `true` if [commit](Self::commit) is a root commit, or the boundary of a limited blame
+    pub boundary:       bool,
+
+    _nonexhaustive:     (),
+}
+
+/// Headers git only reports the first time it mentions a given commit
+#[derive(Clone, Default)]
+struct CommitInfo {
+    author:         Vec<u8>,
+    author_mail:    Vec<u8>,
+    author_time:    Vec<u8>,
+    author_tz:      Vec<u8>,
+    committer:      Vec<u8>,
+    committer_mail: Vec<u8>,
+    committer_time: Vec<u8>,
+    committer_tz:   Vec<u8>,
+    summary:        Vec<u8>,
+}
+
+impl CommitInfo {
+    fn signature(name: &[u8], mail: &[u8], time: &[u8], tz: &[u8]) -> Signature {
+        let mut raw = name.to_vec();
+        raw.push(b' ');
+        raw.extend_from_slice(mail);
+        raw.push(b' ');
+        raw.extend_from_slice(time);
+        raw.push(b' ');
+        raw.extend_from_slice(tz);
+        Signature::parse_lenient(&raw)
+    }
+}
+
+/// Streams [BlameHunk]s from `git blame --incremental`, created by [Repository::blame]
+pub(crate) struct BlameHunks {
+    child:      GitChild,
+    stdout:     BufReader<ChildStdout>,
+    commits:    HashMap<commit::Hash, CommitInfo>,
+    done:       bool,
+}
+
+impl BlameHunks {
+    pub(crate) fn new(mut child: GitChild) -> Self {
+        let stdout = BufReader::new(child.child.stdout.take().unwrap());
+        Self { child, stdout, commits: HashMap::new(), done: false }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.done = true;
+        Ok(self.child.wait()?)
+    }
+}
+
+/// Read a line without it's trailing '\n', or `None` at EOF
+fn read_line(stdout: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    if stdout.read_until(b'\n', &mut line)? == 0 { return Ok(None); }
+    if line.last() == Some(&b'\n') { line.pop(); }
+    Ok(Some(line))
+}
+
+/// Read the next hunk of `git blame --incremental` output, or `None` at EOF.  `commits` remembers headers git only sends once per commit.
+fn read_hunk(stdout: &mut impl BufRead, commits: &mut HashMap<commit::Hash, CommitInfo>) -> io::Result<Option<BlameHunk>> {
+    let header = match read_line(stdout)? { Some(line) => line, None => return Ok(None) };
+    let bad = |line: &[u8]| io::Error::new(io::ErrorKind::InvalidData, format!("git blame --incremental returned unexpected line {:?}", String::from_utf8_lossy(line)));
+
+    let header_str = std::str::from_utf8(&header).map_err(|_| bad(&header))?;
+    let mut fields = header_str.split(' ');
+    let commit          = commit::Hash::from_str(fields.next().ok_or_else(|| bad(&header))?).map_err(GitError::from)?;
+    let original_line   = fields.next().and_then(|f| f.parse().ok()).ok_or_else(|| bad(&header))?;
+    let final_line      = fields.next().and_then(|f| f.parse().ok()).ok_or_else(|| bad(&header))?;
+    let lines           = fields.next().and_then(|f| f.parse().ok()).ok_or_else(|| bad(&header))?;
+
+    let mut info        = commits.get(&commit).cloned().unwrap_or_default();
+    let mut previous    = None;
+    let mut boundary    = false;
+    let original_path;
+
+    loop {
+        let line = read_line(stdout)?.ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "git blame --incremental ended mid-hunk"))?;
+        let sp = line.iter().position(|b| *b == b' ').unwrap_or(line.len());
+        let (key, value) = (&line[..sp], line[(sp+1).min(line.len())..].to_vec());
+        match key {
+            b"author"           => info.author          = value,
+            b"author-mail"      => info.author_mail     = value,
+            b"author-time"      => info.author_time     = value,
+            b"author-tz"        => info.author_tz       = value,
+            b"committer"        => info.committer       = value,
+            b"committer-mail"   => info.committer_mail  = value,
+            b"committer-time"   => info.committer_time  = value,
+            b"committer-tz"     => info.committer_tz    = value,
+            b"summary"          => info.summary         = value,
+            b"boundary"         => boundary             = true,
+            b"previous"         => {
+                let sp = value.iter().position(|b| *b == b' ').ok_or_else(|| bad(&line))?;
+                let hash = std::str::from_utf8(&value[..sp]).map_err(|_| bad(&line))?;
+                previous = Some((commit::Hash::from_str(hash).map_err(GitError::from)?, Name::from(unquote_c_style(&value[sp+1..]))));
+            },
+            b"filename"         => { original_path = Name::from(unquote_c_style(&value)); break; },
+            _                   => {}, // e.g. "ignored", "unblamable"
+        }
+    }
+
+    let author      = CommitInfo::signature(&info.author,    &info.author_mail,    &info.author_time,    &info.author_tz);
+    let committer   = CommitInfo::signature(&info.committer, &info.committer_mail, &info.committer_time, &info.committer_tz);
+    let summary     = Name::from(info.summary.clone());
+    commits.insert(commit.clone(), info);
+
+    Ok(Some(BlameHunk { commit, original_line, final_line, lines, author, committer, summary, original_path, previous, boundary, _nonexhaustive: () }))
+}
+
+impl Iterator for BlameHunks {
+    type Item = io::Result<BlameHunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None; }
+
+        match read_hunk(&mut self.stdout, &mut self.commits) {
+            Ok(Some(hunk))  => Some(Ok(hunk)),
+            Ok(None)        => self.finish().err().map(Err),
+            Err(e)          => { let _ = self.child.kill(); let _ = self.finish(); Some(Err(e)) },
+        }
+    }
+}
+
+impl Drop for BlameHunks {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.child.kill();
+            let _ = self.finish();
+        }
+    }
+}
+
+
+
+/// Undo git's C-style quoting of unusual paths (e.g. `"tab\there"`).  Unquoted paths are returned as-is.
+fn unquote_c_style(raw: &[u8]) -> Vec<u8> {
+    if raw.len() < 2 || raw[0] != b'"' || raw[raw.len()-1] != b'"' { return raw.to_vec(); }
+    let raw = &raw[1..raw.len()-1];
+    let mut out = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] != b'\\' || i+1 == raw.len() { out.push(raw[i]); i += 1; continue; }
+        let esc = raw[i+1];
+        i += 2;
+        out.push(match esc {
+            b'a' => 0x07, b'b' => 0x08, b't' => b'\t', b'n' => b'\n', b'v' => 0x0B, b'f' => 0x0C, b'r' => b'\r',
+            b'0' ..= b'3' if raw[i..].len() >= 2 && raw[i..i+2].iter().all(|d| (b'0' ..= b'7').contains(d)) => {
+                let octal = [esc, raw[i], raw[i+1]];
+                i += 2;
+                octal.iter().fold(0u8, |n, d| n.wrapping_mul(8).wrapping_add(d.wrapping_sub(b'0')))
+            },
+            other => other, // '\\', '"'
+        });
+    }
+    out
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    #[test] fn unquote() {
+        assert_eq!(unquote_c_style(b"plain"),                   b"plain");
+        assert_eq!(unquote_c_style(b"\"tab\\there\""),          b"tab\there");
+        assert_eq!(unquote_c_style(b"\"q\\\"b\\\\s\""),         b"q\"b\\s");
+        assert_eq!(unquote_c_style(b"\"caf\\303\\251\""),       "café".as_bytes());
+        assert_eq!(unquote_c_style(b"\"\\0\""),                 b"0");     // too short for octal
+        assert_eq!(unquote_c_style(b"\"\\18x\""),               b"18x");   // not octal digits
+        assert_eq!(unquote_c_style(b"\"trailing\\\""),          b"trailing\\");
+        assert_eq!(unquote_c_style(b"\""),                      b"\"");
+    }
+
+    #[test] fn read_incremental() {
+        let output = b"\
+12af0fe8b3027dd5ea83a50edab8b76a5eeaa79c 92 92 1
+author Some Name
+author-mail <some@email.com>
+author-time 1600000000
+author-tz -0700
+committer Other
+committer-mail <other@email.com>
+committer-time 1600000100
+committer-tz +0100
+summary Second
+previous ed3267d2c3f826daa35bc36285936c5ff7869158 \"old\\tname\"
+filename new
+ed3267d2c3f826daa35bc36285936c5ff7869158 1 1 91
+author A
+author-mail <a@b>
+author-time 1
+author-tz +0000
+committer A
+committer-mail <a@b>
+committer-time bogus
+committer-tz +0000
+summary First
+boundary
+filename old
+12af0fe8b3027dd5ea83a50edab8b76a5eeaa79c 93 93 2
+filename new
+";
+        let mut reader = &output[..];
+        let mut commits = HashMap::new();
+
+        let hunk = read_hunk(&mut reader, &mut commits).unwrap().unwrap();
+        assert_eq!(hunk.commit.to_string(), "12af0fe8b3027dd5ea83a50edab8b76a5eeaa79c");
+        assert_eq!((hunk.original_line, hunk.final_line, hunk.lines), (92, 92, 1));
+        assert_eq!(hunk.author.name,            "Some Name");
+        assert_eq!(hunk.author.offset_minutes,  -420);
+        assert_eq!(hunk.committer.email,        "other@email.com");
+        assert_eq!(hunk.committer.time,         1600000100);
+        assert_eq!(hunk.summary,                "Second");
+        assert_eq!(hunk.original_path,          "new");
+        let (previous, previous_path) = hunk.previous.unwrap();
+        assert_eq!(previous.to_string(),        "ed3267d2c3f826daa35bc36285936c5ff7869158");
+        assert_eq!(previous_path,               "old\tname");
+        assert!(!hunk.boundary);
+
+        let hunk = read_hunk(&mut reader, &mut commits).unwrap().unwrap();
+        assert_eq!((hunk.original_line, hunk.lines), (1, 91));
+        assert!(hunk.boundary);
+        assert!(hunk.previous.is_none());
+        assert_eq!(hunk.committer.time,         0); // malformed, tolerated like git does
+
+        let hunk = read_hunk(&mut reader, &mut commits).unwrap().unwrap(); // headers remembered from the first hunk
+        assert_eq!((hunk.final_line, hunk.lines), (93, 2));
+        assert_eq!(hunk.author.name,            "Some Name");
+        assert_eq!(hunk.summary,                "Second");
+
+        assert!(read_hunk(&mut reader, &mut commits).unwrap().is_none());
+    }
+
+    #[test] fn read_incremental_malformed() {
+        let mut commits = HashMap::new();
+        let err = |mut output: &[u8], commits: &mut HashMap<_, _>| read_hunk(&mut output, commits).unwrap_err().kind();
+        assert_eq!(err(b"not a header\n", &mut commits),                                                io::ErrorKind::InvalidData);
+        assert_eq!(err(b"12af0fe8b3027dd5ea83a50edab8b76a5eeaa79c 1 1\n", &mut commits),                io::ErrorKind::InvalidData);
+        assert_eq!(err(b"12af0fe8b3027dd5ea83a50edab8b76a5eeaa79c 1 1 1\nauthor A\n", &mut commits),   io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -10,8 +10,9 @@ use std::path::Path;
 
 /// A named reference to a commit (e.g. "master" => "074d881e29cc3bff82da905adcde2aea7cb5b165")
 pub struct Branch {
-    pub(crate) name:    OsString,
-    pub(crate) commit:  commit::Hash
+    pub(crate) name:        OsString,
+    pub(crate) commit:      commit::Hash,
+    pub(crate) alias_of:    Option<OsString>,
 }
 
 impl Branch {
@@ -20,11 +21,17 @@ impl Branch {
 
     /// [Hash](commit::Hash) of the [Commit] this branch points to (e.g. "074d881e29cc3bff82da905adcde2aea7cb5b165")
     pub fn commit(&self) -> &commit::Hash { &self.commit }
+
+    /// If this branch is a symbolic ref, the full name of the ref it's an alias of (e.g. "origin/HEAD" => "refs/remotes/origin/master")
+    pub fn alias_of(&self) -> Option<&OsStr> { self.alias_of.as_deref() }
 }
 
 impl Debug for Branch {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-        write!(fmt, "Branch({:?} => \"{}\")", self.name, self.commit)
+        match self.alias_of.as_ref() {
+            Some(alias_of)  => write!(fmt, "Branch({:?} => {:?} => \"{}\")", self.name, alias_of, self.commit),
+            None            => write!(fmt, "Branch({:?} => \"{}\")", self.name, self.commit),
+        }
     }
 }
 
@@ -52,7 +59,7 @@ impl Debug for BranchRef<'_> {
 
 
 
-pub(crate) fn gather_branches<T>(parent_name: &OsStr, parent_path: &Path, branches: &mut BTreeMap<OsString, generic::Hash<T>>) -> io::Result<()> {
+pub(crate) fn gather_branches<T>(parent_name: &OsStr, parent_path: &Path, branches: &mut BTreeMap<OsString, generic::Hash<T>>, symbolic: &mut BTreeMap<OsString, OsString>) -> io::Result<()> {
     let dir = match parent_path.read_dir() {
         Ok(dir)     => dir,
         Err(ref e)  if e.kind() == io::ErrorKind::NotFound && parent_name.is_empty() => return Ok(()),
@@ -70,13 +77,17 @@ pub(crate) fn gather_branches<T>(parent_name: &OsStr, parent_path: &Path, branch
         name.push(e.file_name());
 
         if meta.is_dir() {
-            gather_branches(&name, &full_path, branches)?;
+            gather_branches(&name, &full_path, branches, symbolic)?;
         } else if meta.is_file() {
             let blob = std::fs::read_to_string(&full_path)?;
 
             // Don't choke on e.g. refs/remotes/origin/HEAD:
             // "ref: refs/remotes/origin/master\n"
-            if blob.starts_with("ref: ") { continue; }
+            if let Some(target) = blob.strip_prefix("ref: ") {
+                branches.remove(&name); // loose symbolic refs override packed refs too
+                symbolic.insert(name, OsString::from(target.trim()));
+                continue;
+            }
 
             branches.insert(name, generic::Hash::from_str(blob.trim())?);
         }
@@ -0,0 +1,163 @@
+use crate::*;
+
+use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
+use std::process::{ChildStdin, ChildStdout, Stdio};
+use std::sync::Mutex;
+
+
+
+/// Long-lived `git cat-file --batch` / `git cat-file --batch-check` sessions, lazily spawned and shared between threads.
+///
+/// Any I/O or protocol error tears down the offending session - it'll be respawned on next use.
+/// A panic while a session is locked disables that session for good (subsequent requests fail.)
+/// Callers are expected to fall back on one-shot `git cat-file` processes ([CatFileReader]) when these return [Err].
+#[derive(Default)]
+pub(crate) struct CatFileBatch {
+    contents:   Mutex<Option<BatchProcess>>,
+    check:      Mutex<Option<BatchProcess>>,
+}
+
+impl CatFileBatch {
+    /// Request `hash` from `git cat-file --batch`.  `Ok(None)` if the object is missing.
+    pub fn contents(&self, repository: &Repository, hash: &unknown::Hash) -> io::Result<Option<(FileType, Vec<u8>)>> {
+        Self::request(&self.contents, repository, "--batch", hash, |process, ty, size| {
+            let mut data = vec![0u8; size as usize];
+            process.stdout().read_exact(&mut data[..])?;
+            let mut lf = [0u8];
+            process.stdout().read_exact(&mut lf[..])?;
+            if lf[0] != b'\n' { return Err(io::Error::new(io::ErrorKind::InvalidData, "git cat-file --batch contents not newline terminated")); }
+            Ok((ty, data))
+        })
+    }
+
+    /// Request `hash` from `git cat-file --batch-check`.  `Ok(None)` if the object is missing.
+    pub fn info(&self, repository: &Repository, hash: &unknown::Hash) -> io::Result<Option<(FileType, u64)>> {
+        Self::request(&self.check, repository, "--batch-check", hash, |_process, ty, size| Ok((ty, size)))
+    }
+
+    fn request<R>(
+        session:    &Mutex<Option<BatchProcess>>,
+        repository: &Repository,
+        mode:       &str,
+        hash:       &unknown::Hash,
+        body:       impl FnOnce(&mut BatchProcess, FileType, u64) -> io::Result<R>,
+    ) -> io::Result<Option<R>> {
+        let mut session = match session.lock() {
+            Ok(session)     => session,
+            Err(poisoned)   => { // a panic mid-request may have desynchronized the session: drop it, and leave the rest to one-shot processes
+                *poisoned.into_inner() = None;
+                return Err(io::Error::new(io::ErrorKind::Other, format!("git cat-file {} session poisoned by an earlier panic", mode)));
+            },
+        };
+        if session.is_none() { *session = Some(BatchProcess::spawn(repository, mode)?); }
+
+        let result = {
+            let process = session.as_mut().unwrap();
+            process.header(hash).and_then(|header| match header {
+                None                => Ok(None),
+                Some((ty, size))    => body(process, ty, size).map(Some),
+            })
+        };
+        if result.is_err() { *session = None; } // stream is likely desynchronized, respawn on next request
+        result
+    }
+}
+
+
+
+struct BatchProcess {
+    child:  GitChild,
+    stdin:  Option<ChildStdin>,
+    stdout: Option<BufReader<ChildStdout>>, // only `None` while dropping
+}
+
+impl BatchProcess {
+    fn spawn(repository: &Repository, mode: &str) -> io::Result<Self> {
+        let mut child = GitChild::spawn(repository.git()
+            .args(&["cat-file", mode])
+            .stdin (Stdio::piped())
+            .stdout(Stdio::piped())
+        )?;
+        let stdin   = child.child.stdin.take();
+        let stdout  = child.child.stdout.take().map(BufReader::new);
+        Ok(Self { child, stdin, stdout })
+    }
+
+    fn stdout(&mut self) -> &mut BufReader<ChildStdout> {
+        self.stdout.as_mut().expect("BatchProcess::stdout used after drop")
+    }
+
+    /// Write `hash` and parse the "\<hash\> \<type\> \<size\>" or "\<hash\> missing" response
+    fn header(&mut self, hash: &unknown::Hash) -> io::Result<Option<(FileType, u64)>> {
+        let hash = HashTempStr::new(hash);
+        {
+            let stdin = self.stdin.as_mut().ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "git cat-file --batch stdin closed"))?;
+            stdin.write_all(hash.bytes())?;
+            stdin.write_all(b"\n")?;
+            stdin.flush()?;
+        }
+
+        let mut line = String::new();
+        if self.stdout().read_line(&mut line)? == 0 {
+            return Err(match self.child.wait() { // report git's own complaint (e.g. "fatal: not a git repository") if it has one
+                Err(e)  => e.into(),
+                Ok(())  => io::Error::new(io::ErrorKind::UnexpectedEof, "git cat-file --batch exited unexpectedly"),
+            });
+        }
+        let mut fields = line.trim_end_matches('\n').split(' ');
+        let bad_header = || io::Error::new(io::ErrorKind::InvalidData, format!("git cat-file --batch returned unexpected header {:?}", line));
+
+        if fields.next() != Some(hash.as_str()) { return Err(bad_header()); }
+        let ty = fields.next().ok_or_else(bad_header)?;
+        if ty == "missing" { return Ok(None); }
+        let size = fields.next().and_then(|s| s.parse().ok()).ok_or_else(bad_header)?;
+        if fields.next().is_some() { return Err(bad_header()); }
+        Ok(Some((FileType::from(ty), size)))
+    }
+}
+
+impl Drop for BatchProcess {
+    fn drop(&mut self) {
+        drop(self.stdin.take());  // EOF on stdin lets git exit cleanly...
+        drop(self.stdout.take()); // ...and a closed stdout unblocks it if we abandoned a response midway
+        let _ = self.child.wait();
+    }
+}
+
+
+
+/// The contents of a `git cat-file [type] [hash]` request, from either a [CatFileBatch] session or a one-shot [CatFileReader].
+pub(crate) enum CatFileContents {
+    Batch(Cursor<Vec<u8>>),
+    Process(CatFileReader),
+}
+
+impl Read for CatFileContents {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            CatFileContents::Batch(c)   => c.read(buf),
+            CatFileContents::Process(p) => p.read(buf),
+        }
+    }
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    #[test] fn poisoned_session() {
+        let repository  = Repository::from_path(".").unwrap();
+        let head        = repository.rev_parse("HEAD").unwrap();
+        let batch       = CatFileBatch::default();
+        assert_eq!(batch.info(&repository, &head).unwrap().unwrap().0, FileType::Commit);
+
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _session = batch.check.lock().unwrap();
+            panic!("poison the --batch-check session");
+        }));
+        assert!(batch.info(&repository, &head).is_err());
+        assert!(matches!(batch.check.lock(), Err(poisoned) if poisoned.get_ref().is_none())); // the desynchronized process was dropped
+        assert!(batch.contents(&repository, &head).unwrap().is_some()); // other sessions are unaffected
+    }
+}
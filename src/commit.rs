@@ -2,7 +2,8 @@
 
 use crate::*;
 
-use std::io::{self, BufRead, BufReader};
+use std::borrow::Cow;
+use std::io::{self, Read};
 
 
 
@@ -25,6 +26,7 @@ pub type Hash = generic::Hash<Commit>;
 /// assert_eq!(commit.tree, htree);
 /// assert_eq!(commit.parents.len(), 0); // initial commit
 /// ```
+#[derive(Debug)]
 pub struct Commit {
     /// The [Hash](commit::Hash) representing this [Commit]
     pub hash:           commit::Hash,
@@ -39,43 +41,92 @@ pub struct Commit {
     /// Vanilla boring [Commit]s have 1 parent, the previous commit.
     pub parents:        Vec<commit::Hash>,
 
-    //authors:        Vec<String>,
-    //committers:     Vec<String>,
-    //description:    String,
+    /// Who originally wrote the changes, and when.  Leniently parsed: malformed or missing (in some very old commits) signatures don't fail [read](Self::read).
+    pub author:         Signature,
+
+    /// Who last committed the changes (e.g. after a rebase or cherry-pick), and when.  Leniently parsed like [author](Self::author).
+    pub committer:      Signature,
+
+    /// The "encoding" header (e.g. "ISO-8859-1"), if any.  Absent means UTF-8.
+    pub encoding:       Option<Name>,
+
+    /// The commit message, exactly as stored.  See [message](Self::message) to decode it.
+    pub raw_message:    Vec<u8>,
+
+    /// Any other headers (e.g. "gpgsig", "mergetag"), in order.  Multi-line values are joined with "\n".
+    pub extra_headers:  Vec<(Name, Name)>,
+
     _nonexhaustive:     (),
 }
 
 impl Commit {
     /// [Read] a local [Commit] from a given [Repository]
     ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository  = Repository::from_path(".").unwrap();
+    /// let head        = repository.rev_parse_commit("HEAD").unwrap();
+    /// let commit      = Commit::read(&repository, &head).unwrap();
+    /// println!("{} <{}>", commit.author.name.as_str_lossy(), commit.author.email.as_str_lossy());
+    /// println!("{}", commit.message().lines().next().unwrap_or(""));
+    /// assert!(commit.committer.time > 0);
+    /// ```
+    ///
     /// [Read]:         std::io::Read
     pub fn read(repository: &Repository, hash: &commit::Hash) -> io::Result<Self> {
-        let mut tree : Option<tree::Hash> = None;
-        let mut parents : Vec<commit::Hash> = Vec::new();
-
-        for line in BufReader::new(repository.cat_file_commit(&hash)?).lines() {
-            let line = line?;
-            let line = line.trim();
-            if line.is_empty() { break } // commit message follows, but we're not parsing that at the moment
-
-            if line.starts_with("tree ") {
-                if tree.is_some() { return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Multiple tree s specified by commit {}", hash))); }
-                tree = Some(tree::Hash::from_str(&line[5..])?);
-            } else if line.starts_with("parent ") {
-                parents.push(commit::Hash::from_str(&line[7..])?);
-            } else {
-                // author, committer, ...
+        let mut data = Vec::new();
+        repository.cat_file_commit(hash)?.read_to_end(&mut data)?;
+        let (headers, message) = parse_object_headers(&data[..]);
+
+        let mut tree        : Option<tree::Hash>    = None;
+        let mut parents     : Vec<commit::Hash>     = Vec::new();
+        let mut author      : Option<Signature>     = None;
+        let mut committer   : Option<Signature>     = None;
+        let mut encoding    : Option<Name>          = None;
+        let mut extra_headers = Vec::new();
+
+        for (key, value) in headers {
+            let utf8 = || std::str::from_utf8(&value[..]).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("non-utf8 {} header in commit {}", String::from_utf8_lossy(&key), hash)));
+            match &key[..] {
+                b"tree" => {
+                    if tree.is_some() { return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Multiple tree s specified by commit {}", hash))); }
+                    tree = Some(tree::Hash::from_str(utf8()?)?);
+                },
+                b"parent"       => parents.push(commit::Hash::from_str(utf8()?)?),
+                b"author"       => author       = Some(Signature::parse_lenient(&value[..])),
+                b"committer"    => committer    = Some(Signature::parse_lenient(&value[..])),
+                b"encoding"     => encoding     = Some(Name::from(value)),
+                _other          => extra_headers.push((Name::from(key), Name::from(value))),
             }
         }
 
-        let tree = tree.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("treeless commit {}", hash)))?;
+        let tree        = tree      .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("treeless commit {}", hash)))?;
+        let author      = author    .unwrap_or_else(|| Signature::parse_lenient(b""));
+        let committer   = committer .unwrap_or_else(|| Signature::parse_lenient(b""));
 
         Ok(Self {
             hash: hash.clone(),
             tree,
             parents,
+            author,
+            committer,
+            raw_message: message.to_vec(),
+            encoding,
+            extra_headers,
 
             _nonexhaustive: ()
         })
     }
+
+    /// The commit message, decoded according to [encoding](Self::encoding) (invalid sequences are replaced with U+FFFD).
+    /// Only UTF-8 and Latin-1 are understood - anything else is decoded as (lossy) UTF-8.
+    pub fn message(&self) -> Cow<'_, str> {
+        let encoding = self.encoding.as_ref().map(|e| e.as_str_lossy().to_ascii_lowercase()).unwrap_or_default();
+        match &encoding[..] {
+            "iso-8859-1" | "iso8859-1" | "latin1" | "latin-1"   => Cow::Owned(self.raw_message.iter().map(|b| char::from(*b)).collect()),
+            _                                                   => String::from_utf8_lossy(&self.raw_message[..]),
+        }
+    }
 }
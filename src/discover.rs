@@ -0,0 +1,141 @@
+use crate::*;
+
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+
+
+
+/// Options for [Repository::discover](crate::Repository::discover)
+#[derive(Clone, Debug, Default)]
+pub struct DiscoverOptions {
+    /// Don't search parent directories on other filesystems (like git does unless `GIT_DISCOVERY_ACROSS_FILESYSTEM` is set.)  Only supported on unix.
+    pub one_filesystem:         bool,
+
+    /// Don't search these directories or their parents, in addition to those in `GIT_CEILING_DIRECTORIES`
+    pub ceiling_directories:    Vec<PathBuf>,
+
+    /// Ignore `GIT_DIR`, `GIT_WORK_TREE`, and `GIT_CEILING_DIRECTORIES` (e.g. when they were set for a hook in another repository)
+    pub ignore_env:             bool,
+}
+
+/// Where [discover] found a (validated) repository
+pub(crate) struct Discovered {
+    pub(crate) dot_git:     PathBuf,
+    pub(crate) work_tree:   Option<PathBuf>,
+}
+
+/// Find the repository containing `dir`, like `git rev-parse --git-dir --show-toplevel` would
+pub(crate) fn discover(dir: &Path, options: &DiscoverOptions) -> io::Result<Discovered> {
+    let cwd = env::current_dir()?;
+    let dir = canonicalize(&cwd.join(dir))?;
+
+    let var = |name: &str| if options.ignore_env { None } else { env::var_os(name).filter(|v| !v.is_empty()) };
+    let work_tree_override = var("GIT_WORK_TREE").map(|wt| cwd.join(wt)); // honored even when discovering the git directory
+    if let Some(git_dir) = var("GIT_DIR") {
+        let dot_git = resolve_dot_git(cwd.join(git_dir))?;
+        validate_git_dir(&dot_git)?;
+        let work_tree = work_tree(&dot_git, work_tree_override, Some(cwd))?; // like git, assume the current directory is the top of the work tree
+        return Ok(Discovered { dot_git, work_tree });
+    }
+
+    let mut ceilings = options.ceiling_directories.iter().map(|c| cwd.join(c)).collect::<Vec<_>>();
+    if let Some(env_ceilings) = var("GIT_CEILING_DIRECTORIES") {
+        ceilings.extend(env::split_paths(&env_ceilings).filter(|c| c.is_absolute()));
+    }
+    let ceilings = ceilings.into_iter().map(|c| canonicalize(&c).unwrap_or(c)).collect::<Vec<_>>();
+    let device = if options.one_filesystem { device_of(&dir) } else { None };
+
+    let mut candidate = dir.as_path();
+    loop {
+        let dot_git = candidate.join(".git");
+        if dot_git.is_file() { // unlike an invalid .git directory, git doesn't search past an invalid gitfile
+            let dot_git = resolve_dot_git(dot_git)?;
+            validate_git_dir(&dot_git)?;
+            return Ok(Discovered { work_tree: work_tree(&dot_git, work_tree_override, Some(candidate.to_path_buf()))?, dot_git });
+        }
+        if is_git_dir(&dot_git)? { return Ok(Discovered { work_tree: work_tree(&dot_git, work_tree_override, Some(candidate.to_path_buf()))?, dot_git }); }
+        if is_git_dir(candidate)? { return Ok(Discovered { dot_git: candidate.to_path_buf(), work_tree: work_tree(candidate, work_tree_override, None)? }); }
+
+        let parent = match candidate.parent() { Some(p) => p, None => break };
+        if ceilings.iter().any(|c| c == parent) { break; }
+        if device.is_some() && device_of(parent) != device { break; }
+        candidate = parent;
+    }
+
+    Err(GitError::NotARepository(dir).into())
+}
+
+/// Pick the work tree of the (validated) `dot_git` like git does: `GIT_WORK_TREE` if set, none if `core.bare`, `core.worktree` (relative to `dot_git`) if set, otherwise `default`.
+/// Linked worktrees ignore `core.bare` and `core.worktree`, which belong to the main worktree.
+fn work_tree(dot_git: &Path, work_tree_override: Option<PathBuf>, default: Option<PathBuf>) -> io::Result<Option<PathBuf>> {
+    if work_tree_override.is_some() { return Ok(work_tree_override); }
+    if common_dir(dot_git)? != dot_git { return Ok(default); }
+    let repository = Repository::from_valid_dot_git(dot_git.to_path_buf(), None)?;
+    if repository.config_get("core.bare", Some("bool"))?.as_deref() == Some("true") { return Ok(None); }
+    Ok(repository.config_get("core.worktree", Some("path"))?.map(|wt| dot_git.join(wt)).or(default))
+}
+
+/// Check that `dir` is a `.git` directory or bare repository: a valid `HEAD`, plus `objects/` and `refs/` in it's [common_dir] (linked worktrees keep those in the main repository.)
+///
+/// Fails with [GitError::NotARepository] ([NotFound](io::ErrorKind::NotFound)) if not, but passes along other errors such as [PermissionDenied](io::ErrorKind::PermissionDenied).
+pub(crate) fn validate_git_dir(dir: &Path) -> io::Result<()> {
+    let not_a_repository = || io::Error::from(GitError::NotARepository(dir.to_path_buf()));
+    let or_not_a_repository = |e: io::Error| if e.kind() == io::ErrorKind::PermissionDenied { e } else { not_a_repository() };
+
+    let head = std::fs::read_to_string(dir.join("HEAD")).map_err(or_not_a_repository)?;
+    let head = head.trim_end();
+    if !head.starts_with("ref: refs/") && unknown::Hash::from_str(head).is_err() { return Err(not_a_repository()); }
+
+    let common = common_dir(dir).map_err(or_not_a_repository)?;
+    for sub in ["objects", "refs"].iter() {
+        if !std::fs::metadata(common.join(sub)).map_err(or_not_a_repository)?.is_dir() { return Err(not_a_repository()); }
+    }
+    Ok(())
+}
+
+/// Is `dir` a valid git directory?  Like [validate_git_dir], but `Ok(false)` instead of [GitError::NotARepository].
+fn is_git_dir(dir: &Path) -> io::Result<bool> {
+    match validate_git_dir(dir) {
+        Ok(())                                                                          => Ok(true),
+        Err(ref e) if matches!(GitError::find(e), Some(GitError::NotARepository(_)))    => Ok(false),
+        Err(e)                                                                          => Err(e),
+    }
+}
+
+/// [Path::canonicalize], but without the `\\?\` prefix Windows adds (e.g. `\\?\C:\repo` becomes `C:\repo`) where the path is usable without it, like the `dunce` crate
+#[cfg(windows)] pub(crate) fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    let canonical = path.canonicalize()?;
+    let simplified = match canonical.to_str() {
+        Some(s) if s.starts_with(r"\\?\UNC\")  => Some(format!(r"\\{}", &s[r"\\?\UNC\".len()..])),
+        Some(s) if s.starts_with(r"\\?\")       => {
+            let rest = &s[r"\\?\".len()..];
+            let drive = matches!(rest.as_bytes(), [d, b':', b'\\', ..] if d.is_ascii_alphabetic());
+            if drive && rest.len() < 260 && !rest.contains(&['/', '?', '*', '<', '>', '|', '"'][..]) && !rest.split('\\').any(is_reserved_name) { Some(rest.to_string()) } else { None }
+        },
+        _ => None,
+    };
+    Ok(simplified.map_or(canonical, PathBuf::from))
+}
+
+/// Is `component` a DOS device name (e.g. "CON", "nul.txt"), which only means the file with a `\\?\` prefix?
+#[cfg(windows)] fn is_reserved_name(component: &str) -> bool {
+    let stem = component.split('.').next().unwrap_or("").trim_end_matches(' ').to_ascii_uppercase();
+    matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL" | "CONIN$" | "CONOUT$")
+        || (stem.len() == 4 && (stem.starts_with("COM") || stem.starts_with("LPT")) && stem.as_bytes()[3].is_ascii_digit())
+        || component.ends_with('.') || component.ends_with(' ')
+}
+
+/// [Path::canonicalize], but without the `\\?\` prefix Windows adds (e.g. `\\?\C:\repo` becomes `C:\repo`) where the path is usable without it, like the `dunce` crate
+#[cfg(not(windows))] pub(crate) fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    path.canonicalize()
+}
+
+#[cfg(unix)] fn device_of(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    path.metadata().ok().map(|m| m.dev())
+}
+
+#[cfg(not(unix))] fn device_of(_path: &Path) -> Option<u64> {
+    None
+}
@@ -1,6 +1,6 @@
 use std::fmt::{self, Debug, Display, Formatter};
 
-/// The type of some git content ("blob", "commit", or "tree")
+/// The type of some git content ("blob", "commit", "tag", or "tree")
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum FileType {
     /// A Blob.
@@ -9,6 +9,9 @@ pub enum FileType {
     /// A [Commit](crate::Commit).
     Commit,
 
+    /// An annotated [Tag](crate::Tag).
+    Tag,
+
     /// A [Tree](crate::Tree).
     Tree,
 
@@ -16,11 +19,12 @@ pub enum FileType {
 }
 
 impl FileType {
-    /// Return the git-style string for the given file type (e.g. "blob", "commit", or "tree")
+    /// Return the git-style string for the given file type (e.g. "blob", "commit", "tag", or "tree")
     pub fn as_str(&self) -> &str {
         match self {
             FileType::Blob          => "blob",
             FileType::Commit        => "commit",
+            FileType::Tag           => "tag",
             FileType::Tree          => "tree",
             FileType::_Unknown(s)   => s.as_str(),
         }
@@ -32,6 +36,7 @@ impl From<&str> for FileType {
         match t {
             "blob"      => FileType::Blob,
             "commit"    => FileType::Commit,
+            "tag"       => FileType::Tag,
             "tree"      => FileType::Tree,
             _other      => FileType::_Unknown(t.to_owned()),
         }
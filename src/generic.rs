@@ -10,7 +10,7 @@ use std::str::FromStr;
 
 
 
-/// A [SHA-1] or [SHA-256] reference to a git [Commit], [Tree], or Blob
+/// A [SHA-1] or [SHA-256] reference to a git [Commit], [Tree], [Tag], or Blob
 ///
 /// [SHA-1]:    https://en.wikipedia.org/wiki/SHA-1
 /// [SHA-256]:  https://en.wikipedia.org/wiki/SHA-2
@@ -198,12 +198,15 @@ impl<T> Clone for Hash<T> {
     }
 }
 
+/// Formats as lowercase hexadecimal.  A precision (e.g. `{:.7}`) truncates to that many hex digits.
 impl<T> Display for Hash<T> {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-        for b in &self.bytes[..self.len as usize] {
-            write!(fmt, "{:02x}", b)?;
+        let hex = HashTempStr::new(self);
+        let hex = hex.as_str();
+        match fmt.precision() {
+            Some(digits)    => fmt.write_str(&hex[..digits.min(hex.len())]),
+            None            => fmt.write_str(hex),
         }
-        Ok(())
     }
 }
 
@@ -232,10 +235,12 @@ impl<T> FromStr for Hash<T> { fn from_str(s: &str) -> Result<Self, HashParseErro
 
 impl PartialEq<Hash<()>> for Hash<Blob  > { fn eq(&self, other: &Hash<()>) -> bool { self.bytes() == other.bytes() } }
 impl PartialEq<Hash<()>> for Hash<Commit> { fn eq(&self, other: &Hash<()>) -> bool { self.bytes() == other.bytes() } }
+impl PartialEq<Hash<()>> for Hash<Tag   > { fn eq(&self, other: &Hash<()>) -> bool { self.bytes() == other.bytes() } }
 impl PartialEq<Hash<()>> for Hash<Tree  > { fn eq(&self, other: &Hash<()>) -> bool { self.bytes() == other.bytes() } }
 
 impl PartialEq<Hash<Blob  >> for Hash<()> { fn eq(&self, other: &Hash<Blob  >) -> bool { self.bytes() == other.bytes() } }
 impl PartialEq<Hash<Commit>> for Hash<()> { fn eq(&self, other: &Hash<Commit>) -> bool { self.bytes() == other.bytes() } }
+impl PartialEq<Hash<Tag   >> for Hash<()> { fn eq(&self, other: &Hash<Tag   >) -> bool { self.bytes() == other.bytes() } }
 impl PartialEq<Hash<Tree  >> for Hash<()> { fn eq(&self, other: &Hash<Tree  >) -> bool { self.bytes() == other.bytes() } }
 
 
@@ -301,7 +306,7 @@ impl HashTempStr {
 
 
 
-fn ascii_byte_to_hex(b: u8) -> Result<u8, HashParseError> {
+pub(crate) fn ascii_byte_to_hex(b: u8) -> Result<u8, HashParseError> {
     match b {
         b'0' ..= b'9'   => Ok(b - b'0'),
         b'a' ..= b'f'   => Ok(b - b'a' + 10),
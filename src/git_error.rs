@@ -0,0 +1,130 @@
+use crate::*;
+
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+
+
+/// Describes how running `git` (or making sense of what it said) failed.
+/// Convertable to [std::io::Error], [Box]&lt;dyn [std::error::Error]&gt;.
+///
+/// Most of clgit returns [io::Error] for compatibility - use [GitError::find] to recover the details.
+///
+/// # Examples
+///
+/// ```rust
+/// # use clgit::*;
+/// let repository = Repository::from_path(".").unwrap();
+/// let missing = blob::Hash::from_str("0123456789012345678901234567890123456789").unwrap();
+/// let error = repository.cat_file_size(&missing).unwrap_err();
+/// match GitError::find(&error) {
+///     Some(GitError::ObjectNotFound(hash))    => assert_eq!(*hash, missing),
+///     other                                   => panic!("expected ObjectNotFound, got {:?}", other),
+/// }
+/// ```
+#[derive(Debug)]
+pub enum GitError {
+    /// `git` couldn't be started at all (e.g. it isn't installed, or the directory it should run in doesn't exist)
+    Spawn {
+        /// The command line that failed to start
+        command:    String,
+        /// Why it failed to start
+        error:      io::Error,
+    },
+
+    /// `git` exited with a non-zero exit code
+    Exit {
+        /// The command line that failed
+        command:    String,
+        /// The exit code
+        code:       i32,
+        /// Everything `git` wrote to stderr (e.g. "fatal: not a git repository ...")
+        stderr:     String,
+    },
+
+    /// `git` was killed by a signal
+    Signal {
+        /// The command line that was killed
+        command:    String,
+        /// Everything `git` wrote to stderr before dying
+        stderr:     String,
+    },
+
+    /// `git` returned a malformed [Hash](generic::Hash)
+    Parse(HashParseError),
+
+    /// `git version` is older than [GitOptions::min_version]
+    UnsupportedVersion {
+        /// The version of `git` found
+        found:      (u32, u32, u32),
+        /// The minimum version required
+        required:   (u32, u32, u32),
+    },
+
+    /// The path isn't a git repository (or a `.git` directory): it's missing `HEAD`, `objects/`, or `refs/`
+    NotARepository(PathBuf),
+
+    /// The requested object doesn't exist in the repository
+    ObjectNotFound(unknown::Hash),
+
+    /// Communicating with `git` failed for some other reason
+    Io(io::Error),
+}
+
+impl GitError {
+    /// Find the [GitError] an [io::Error] was created from, if any
+    pub fn find(error: &io::Error) -> Option<&GitError> {
+        error.get_ref().and_then(|e| e.downcast_ref::<GitError>())
+    }
+
+    /// The stderr output of a failed `git` process, if any
+    pub fn stderr(&self) -> Option<&str> {
+        match self {
+            GitError::Exit { stderr, .. }   => Some(stderr),
+            GitError::Signal { stderr, .. } => Some(stderr),
+            _                               => None,
+        }
+    }
+}
+
+impl std::error::Error for GitError {}
+
+impl Display for GitError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            GitError::Spawn { command, error }          => write!(fmt, "Unable to run {}: {}", command, error),
+            GitError::Exit { command, code, stderr }    => write!(fmt, "{} exited with code {}: {}", command, code, stderr.trim_end()),
+            GitError::Signal { command, stderr }        => write!(fmt, "{} died by signal: {}", command, stderr.trim_end()),
+            GitError::Parse(e)                          => write!(fmt, "{}", e),
+            GitError::UnsupportedVersion { found, required } => write!(fmt, "git {}.{}.{} is too old, {}.{}.{} or later is required", found.0, found.1, found.2, required.0, required.1, required.2),
+            GitError::NotARepository(path)              => write!(fmt, "not a git repository: {}", path.display()),
+            GitError::ObjectNotFound(hash)              => write!(fmt, "git object {} missing", hash),
+            GitError::Io(e)                             => write!(fmt, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for GitError {
+    fn from(e: io::Error) -> Self { GitError::Io(e) }
+}
+
+impl From<HashParseError> for GitError {
+    fn from(e: HashParseError) -> Self { GitError::Parse(e) }
+}
+
+impl From<GitError> for io::Error {
+    fn from(ge: GitError) -> Self {
+        let kind = match ge {
+            GitError::Io(e)                     => return e,
+            GitError::Spawn { ref error, .. }   => error.kind(),
+            GitError::Exit { .. }               => io::ErrorKind::Other,
+            GitError::Signal { .. }             => io::ErrorKind::Other,
+            GitError::Parse(_)                  => io::ErrorKind::InvalidData,
+            GitError::UnsupportedVersion { .. } => io::ErrorKind::Other,
+            GitError::NotARepository(_)         => io::ErrorKind::NotFound,
+            GitError::ObjectNotFound(_)         => io::ErrorKind::NotFound,
+        };
+        io::Error::new(kind, ge)
+    }
+}
@@ -0,0 +1,119 @@
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+
+
+/// Controls how every `git` process spawned on behalf of a [Repository](crate::Repository) is run, as set by [Repository::with_git_options](crate::Repository::with_git_options)
+///
+/// # Examples
+///
+/// ```rust
+/// # use clgit::*;
+/// let options = GitOptions {
+///     env:            vec![("GIT_CONFIG_NOSYSTEM".into(), "1".into())],
+///     env_remove:     vec!["GIT_INDEX_FILE".into()],
+///     config:         vec![("core.abbrev".into(), "12".into())],
+///     min_version:    Some((2, 0, 0)),
+///     ..Default::default()
+/// };
+/// let repository = Repository::from_path(".").unwrap().with_git_options(options).unwrap();
+/// let head = repository.rev_parse("HEAD").unwrap();
+/// assert_eq!(repository.short_hash(&head).unwrap().len(), 12);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct GitOptions {
+    /// The `git` executable to run, instead of searching `PATH` for "git".  Prefer absolute paths: `git` is run from within the repository.
+    pub executable:     Option<PathBuf>,
+
+    /// Extra environment variables to set (e.g. `GIT_CONFIG_NOSYSTEM=1`)
+    pub env:            Vec<(OsString, OsString)>,
+
+    /// Inherited environment variables to remove (e.g. `GIT_INDEX_FILE`, which git sets for hooks.)  Applied before [env](Self::env).
+    /// `GIT_DIR` and `GIT_WORK_TREE` are always set to the [Repository](crate::Repository)'s own, regardless of these options.
+    pub env_remove:     Vec<OsString>,
+
+    /// Config overrides, passed as `git -c key=value ...`
+    pub config:         Vec<(String, OsString)>,
+
+    /// Fail [Repository::with_git_options](crate::Repository::with_git_options) unless `git version` is at least (major, minor, patch)
+    pub min_version:    Option<(u32, u32, u32)>,
+}
+
+impl GitOptions {
+    /// A `git` [Command] with these options applied, bound to `git_dir` and `work_tree` (`None` for commands that don't need one) via `GIT_DIR` / `GIT_WORK_TREE`.
+    /// Run from `work_tree`, or `git_dir` without one.
+    pub(crate) fn command(&self, git_dir: &Path, work_tree: Option<&Path>) -> Command {
+        let mut c = Command::new(self.executable.as_ref().map_or(OsStr::new("git"), |e| e.as_os_str()));
+        for key in self.env_remove.iter()   { c.env_remove(key); }
+        for (key, value) in self.env.iter() { c.env(key, value); }
+        for (key, value) in self.config.iter() {
+            let mut kv = OsString::from(key);
+            kv.push("=");
+            kv.push(value);
+            c.arg("-c").arg(kv);
+        }
+        c.env("GIT_DIR", git_dir);
+        match work_tree {
+            Some(work_tree) => c.env("GIT_WORK_TREE", work_tree).current_dir(work_tree),
+            None            => c.env_remove("GIT_WORK_TREE").current_dir(git_dir),
+        };
+        c
+    }
+}
+
+
+
+/// Parse the (major, minor, patch) out of `git version` output such as "git version 2.39.2 (Apple Git-143)" or "git version 2.45.1.windows.1"
+pub(crate) fn parse_git_version(output: &str) -> Option<(u32, u32, u32)> {
+    let output = output.trim();
+    if !output.starts_with("git version ") { return None; }
+    let version = output["git version ".len()..].split(' ').next()?;
+    let mut parts = version.split('.').map(|part| {
+        let digits = part.find(|c: char| !c.is_ascii_digit()).unwrap_or(part.len()); // e.g. "0-rc1"
+        part[..digits].parse::<u32>().ok()
+    });
+    let major = parts.next()??;
+    let minor = parts.next().and_then(|p| p).unwrap_or(0);
+    let patch = parts.next().and_then(|p| p).unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    #[test] fn parse_versions() {
+        assert_eq!(parse_git_version("git version 2.39.2 (Apple Git-143)\n"),  Some((2, 39, 2)));
+        assert_eq!(parse_git_version("git version 2.45.1.windows.1"),          Some((2, 45, 1)));
+        assert_eq!(parse_git_version("git version 2.46.0-rc1"),                Some((2, 46, 0)));
+        assert_eq!(parse_git_version("git version 2.47.0.rc0.1.g1234abcd"),    Some((2, 47, 0)));
+        assert_eq!(parse_git_version("git version 3"),                         Some((3, 0, 0)));
+        assert_eq!(parse_git_version("git version 1.8"),                       Some((1, 8, 0)));
+    }
+
+    #[test] fn parse_bad_versions() {
+        assert_eq!(parse_git_version(""),                                      None);
+        assert_eq!(parse_git_version("hg version 2.39.2"),                     None);
+        assert_eq!(parse_git_version("git version "),                          None);
+        assert_eq!(parse_git_version("git version x.y.z"),                     None);
+    }
+
+    #[test] fn ignores_inherited_git_dir() {
+        let other = std::env::temp_dir().join(format!("clgit-other-repository-{}", std::process::id()));
+        assert!(Command::new("git").args(&["init", "-q"]).arg(&other).status().unwrap().success());
+
+        let repository  = crate::Repository::from_path(".").unwrap();
+        let head        = repository.rev_parse("HEAD").unwrap();
+        let leaky       = repository.clone().with_git_options(GitOptions {
+            env: vec![("GIT_DIR".into(), other.join(".git").into()), ("GIT_WORK_TREE".into(), other.clone().into())], // as if inherited from a hook
+            ..Default::default()
+        }).unwrap();
+        let result = (leaky.rev_parse("HEAD"), leaky.status(&Default::default()));
+        std::fs::remove_dir_all(&other).unwrap();
+
+        assert_eq!(result.0.unwrap(), head);
+        assert!(result.1.is_ok());
+    }
+}
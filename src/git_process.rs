@@ -0,0 +1,108 @@
+use crate::*;
+
+use std::io::{self, Read};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread::JoinHandle;
+
+
+
+/// The result of [GitOutput::run]: a finished `git` process, with stdout and stderr captured
+pub(crate) struct GitOutput {
+    pub(crate) command: String,
+    pub(crate) status:  ExitStatus,
+    pub(crate) stdout:  Vec<u8>,
+    pub(crate) stderr:  Vec<u8>,
+}
+
+impl GitOutput {
+    /// Run `command` to completion with stdin closed.  Only failing to start is an error - see [check](Self::check).
+    pub(crate) fn run(command: &mut Command) -> Result<Self, GitError> {
+        let name = command_line(command);
+        let output = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).output();
+        let output = output.map_err(|error| GitError::Spawn { command: name.clone(), error })?;
+        Ok(Self { command: name, status: output.status, stdout: output.stdout, stderr: output.stderr })
+    }
+
+    /// stdout if `git` exited successfully, otherwise [GitError::Exit] or [GitError::Signal]
+    pub(crate) fn check(self) -> Result<Vec<u8>, GitError> {
+        match exit_error(&self.command, self.status, &self.stderr) {
+            None    => Ok(self.stdout),
+            Some(e) => Err(e),
+        }
+    }
+}
+
+/// A running `git` process whose stderr is collected on a background thread, for reporting once it exits
+pub(crate) struct GitChild {
+    pub(crate) command: String,
+    pub(crate) child:   Child,
+    stderr:             Option<JoinHandle<Vec<u8>>>,
+}
+
+impl GitChild {
+    /// Spawn `command` with stderr captured.  Configure stdin/stdout beforehand.
+    pub(crate) fn spawn(command: &mut Command) -> Result<Self, GitError> {
+        let name = command_line(command);
+        let mut child = command.stderr(Stdio::piped()).spawn().map_err(|error| GitError::Spawn { command: name.clone(), error })?;
+        let stderr = child.stderr.take().map(|mut stderr| std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        }));
+        Ok(Self { command: name, child, stderr })
+    }
+
+    /// Wait for `git` to exit, failing with [GitError::Exit] or [GitError::Signal] (including stderr) if it didn't succeed
+    pub(crate) fn wait(&mut self) -> Result<(), GitError> {
+        let status = self.child.wait()?;
+        let stderr = self.stderr.take().and_then(|t| t.join().ok()).unwrap_or_default();
+        match exit_error(&self.command, status, &stderr) {
+            None    => Ok(()),
+            Some(e) => Err(e),
+        }
+    }
+
+    pub(crate) fn kill(&mut self) -> io::Result<()> { self.child.kill() }
+}
+
+
+
+/// `command`'s program and arguments (e.g. `git -c core.abbrev=<redacted> cat-file -t ...`), for error messages.
+/// Unlike [Command]'s [Debug](std::fmt::Debug), this leaves out environment variables and the values of `-c key=value` config overrides, which may be secrets.
+fn command_line(command: &Command) -> String {
+    let mut line = command.get_program().to_string_lossy().into_owned();
+    let mut config = false;
+    for arg in command.get_args() {
+        let arg = arg.to_string_lossy();
+        let arg = match arg.find('=') {
+            Some(eq) if config  => format!("{}=<redacted>", &arg[..eq]),
+            _                   => arg.into_owned(),
+        };
+        config = arg == "-c";
+        line.push(' ');
+        if arg.is_empty() || arg.contains(char::is_whitespace) { line.push_str(&format!("{:?}", arg)); } else { line.push_str(&arg); }
+    }
+    line
+}
+
+fn exit_error(command: &str, status: ExitStatus, stderr: &[u8]) -> Option<GitError> {
+    let command = command.to_string();
+    let stderr  = String::from_utf8_lossy(stderr).into_owned();
+    match status.code() {
+        Some(0)     => None,
+        Some(code)  => Some(GitError::Exit { command, code, stderr }),
+        None        => Some(GitError::Signal { command, stderr }),
+    }
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    #[test] fn command_line_redacts_config() {
+        let mut command = Command::new("git");
+        command.env("GIT_ASKPASS_SECRET", "hunter2").arg("-c").arg("http.extraHeader=Authorization: Bearer hunter2").args(&["log", "--format=%H", "two words", ""]);
+        assert_eq!(command_line(&command), r#"git -c http.extraHeader=<redacted> log --format=%H "two words" """#);
+    }
+}
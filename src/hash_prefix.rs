@@ -0,0 +1,108 @@
+use crate::*;
+
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::FromStr;
+
+
+
+/// An abbreviated [Hash](generic::Hash) (e.g. "02c4f04"): 4 to 64 hexadecimal digits
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::len_without_is_empty)] // never empty: prefixes have at least 4 digits
+pub struct HashPrefix {
+    bytes:      [u8; 32],
+    digits:     u8,
+}
+
+impl HashPrefix {
+    /// Construct a [HashPrefix] from a hexidecimal string of 4 to 64 characters
+    ///
+    /// # Examples
+    /// ```rust
+    /// use clgit::HashPrefix;
+    ///
+    /// for good in [
+    ///     "02c4",
+    ///     "02c4f04",
+    ///     "02C4F0499BCF",
+    ///     "02c4f0499bcf979ad86d9ef5b61ffc51b1394bef",
+    ///     "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+    /// ].iter().cloned() {
+    ///     let prefix = HashPrefix::from_str(good).unwrap_or_else(|e| panic!("Failed to parse {}: {}", good, e));
+    ///     assert_eq!(prefix.len(), good.len());
+    ///     assert_eq!(prefix.to_string(), good.to_ascii_lowercase());
+    /// }
+    ///
+    /// for bad in [
+    ///     "",
+    ///     "02c",      // too short
+    ///     "02c4!",    // invalid character
+    ///     "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef0", // too long
+    /// ].iter().cloned() {
+    ///     assert!(HashPrefix::from_str(bad).is_err(), "Didn't expect to parse {}", bad);
+    /// }
+    /// ```
+    pub fn from_str(s: &str) -> Result<Self, HashParseError> {
+        let ascii = s.as_bytes();
+        match ascii.len() {
+            4 ..= 64    => {},
+            _           => return Err(HashParseError::LengthMismatch),
+        }
+
+        let mut bytes = [0u8; 32];
+        for (i, ch) in ascii.iter().enumerate() {
+            let nibble = generic::ascii_byte_to_hex(*ch)?;
+            bytes[i/2] |= if i % 2 == 0 { nibble << 4 } else { nibble };
+        }
+
+        Ok(Self { bytes, digits: ascii.len() as u8 })
+    }
+
+    /// Abbreviate `hash` to it's first `digits` hexadecimal digits (clamped to 4 ..= the full hash length)
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use clgit::*;
+    /// let hash = unknown::Hash::from_str("02c4f0499bcf979ad86d9ef5b61ffc51b1394bef").unwrap();
+    /// assert_eq!(HashPrefix::from_hash(&hash, 7).to_string(), "02c4f04");
+    /// assert!(HashPrefix::from_hash(&hash, 7).matches(&hash));
+    /// ```
+    pub fn from_hash<T>(hash: &generic::Hash<T>, digits: usize) -> Self {
+        let digits = digits.max(4).min(hash.len() * 2);
+        let mut bytes = [0u8; 32];
+        bytes[..hash.len()].copy_from_slice(hash.bytes());
+        if digits % 2 == 1 { bytes[digits/2] &= 0xF0; }
+        for b in bytes[(digits+1)/2..].iter_mut() { *b = 0; }
+        Self { bytes, digits: digits as u8 }
+    }
+
+    /// Get the number of hexadecimal digits in this prefix (4 ..= 64)
+    pub fn len(&self) -> usize { usize::from(self.digits) }
+
+    /// Returns `true` if `hash` starts with this prefix
+    pub fn matches<T>(&self, hash: &generic::Hash<T>) -> bool {
+        let digits = self.len();
+        if digits > hash.len() * 2 { return false; }
+        let whole = digits / 2;
+        if self.bytes[..whole] != hash.bytes()[..whole] { return false; }
+        digits % 2 == 0 || (self.bytes[whole] & 0xF0) == (hash.bytes()[whole] & 0xF0)
+    }
+}
+
+impl<T> From<&generic::Hash<T>> for HashPrefix {
+    fn from(hash: &generic::Hash<T>) -> Self { Self::from_hash(hash, hash.len() * 2) }
+}
+
+impl Display for HashPrefix {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let hex = b"0123456789abcdef";
+        for i in 0 .. self.len() {
+            let b = self.bytes[i/2];
+            let nibble = if i % 2 == 0 { b >> 4 } else { b & 0xF };
+            write!(fmt, "{}", hex[usize::from(nibble)] as char)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug      for HashPrefix { fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { write!(fmt, "HashPrefix(\"{}\")", self) } }
+impl FromStr    for HashPrefix { fn from_str(s: &str) -> Result<Self, HashParseError> { Self::from_str(s) } type Err = HashParseError; }
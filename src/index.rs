@@ -0,0 +1,413 @@
+use crate::*;
+
+use std::collections::*;
+use std::io;
+
+
+
+/// A parsed git index (the staging area, `.git/index`), as read by [Repository::index]
+#[derive(Clone, Debug)]
+pub struct Index {
+    /// The index format version (2, 3, or 4), or 0 if read via `git ls-files`
+    pub version:        u32,
+
+    /// Every entry, by path.  Usually one entry at stage 0 per path, or entries at stages 1 ..= 3 (sorted by stage) for merge conflicts.
+    pub entries:        BTreeMap<Name, Vec<IndexEntry>>,
+
+    /// The "TREE" extension: [Tree] hashes of still-valid directories, if git recorded any
+    pub cached_trees:   Vec<CachedTree>,
+
+    _nonexhaustive:     (),
+}
+
+/// A single path + stage of an [Index]
+#[derive(Clone, Debug)]
+pub struct IndexEntry {
+    /// The path relative to the root of the work tree (e.g. "src/foo.rs")
+    pub path:       Name,
+
+    /// The staged object.  For submodules this is actually a [Commit], and for sparse directories a [Tree].
+    pub hash:       blob::Hash,
+
+    /// The staged [Mode](tree::Mode)
+    pub mode:       tree::Mode,
+
+    /// 0 normally, or during merge conflicts: 1 (common ancestor), 2 (ours), 3 (theirs)
+    pub stage:      u8,
+
+    /// Assume unchanged (`git update-index --assume-unchanged`)
+    pub assume_valid:   bool,
+
+    /// Not checked out in a sparse checkout (`git update-index --skip-worktree`)
+    pub skip_worktree:  bool,
+
+    /// Added with `git add --intent-to-add`
+    pub intent_to_add:  bool,
+
+    /// Cached file system metadata, used by git to detect changes cheaply.  All zeros if the index was read via `git ls-files`.
+    pub stat:       IndexStat,
+
+    _nonexhaustive: (),
+}
+
+/// File system metadata cached in an [IndexEntry]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IndexStat {
+    /// (seconds, nanoseconds) of the last metadata change
+    pub ctime:  (u32, u32),
+    /// (seconds, nanoseconds) of the last data change
+    pub mtime:  (u32, u32),
+    /// Device
+    pub dev:    u32,
+    /// Inode
+    pub ino:    u32,
+    /// User ID
+    pub uid:    u32,
+    /// Group ID
+    pub gid:    u32,
+    /// File size, truncated to 32 bits
+    pub size:   u32,
+}
+
+/// A directory whose [Tree] hash git has cached in the index ("TREE" extension)
+#[derive(Clone, Debug)]
+pub struct CachedTree {
+    /// The directory's path ("" for the root)
+    pub path:       Name,
+
+    /// How many index entries the directory covers
+    pub entries:    usize,
+
+    /// How many immediate subdirectories the directory has
+    pub subtrees:   usize,
+
+    /// The directory's [Tree] hash
+    pub hash:       tree::Hash,
+
+    _nonexhaustive: (),
+}
+
+impl Index {
+    /// Parse the contents of an index file (versions 2 ..= 4) of a SHA-1 repository.  The trailing checksum isn't verified.
+    ///
+    /// Fails with [ErrorKind::Unsupported](io::ErrorKind::Unsupported) for versions and required extensions this parser doesn't understand,
+    /// including split indexes ("link" extension), whose entries live in another file.
+    pub fn parse(data: &[u8]) -> io::Result<Self> { Self::parse_hash_len(data, 20) }
+
+    /// Like [parse](Self::parse), but for repositories using SHA-256 (`extensions.objectFormat = sha256`)
+    pub fn parse_sha256(data: &[u8]) -> io::Result<Self> { Self::parse_hash_len(data, 32) }
+
+    fn parse_hash_len(data: &[u8], hash_len: usize) -> io::Result<Self> {
+        let bad = |what: &str| io::Error::new(io::ErrorKind::InvalidData, format!("invalid git index: {}", what));
+
+        if data.len() < 12 + hash_len || &data[..4] != b"DIRC" { return Err(bad("missing DIRC header")); }
+        let version = be32(&data[4..]);
+        if !(2 ..= 4).contains(&version) { return Err(io::Error::new(io::ErrorKind::Unsupported, format!("unsupported git index version {}", version))); }
+        let count = be32(&data[8..]) as usize;
+        let end = data.len() - hash_len; // trailing checksum
+
+        let mut index = Self { version, ..Self::empty() };
+        let mut pos = 12;
+        let mut prev_path = Vec::new();
+
+        for _ in 0 .. count {
+            let start = pos;
+            if pos + 42 + hash_len > end { return Err(bad("truncated entry")); }
+            let u = |i: usize| be32(&data[start + 4*i ..]);
+            let stat = IndexStat { ctime: (u(0), u(1)), mtime: (u(2), u(3)), dev: u(4), ino: u(5), uid: u(7), gid: u(8), size: u(9) };
+            let mode = tree::Mode::from_raw(u(6));
+            let hash = blob::Hash::from_bytes(&data[start+40 .. start+40+hash_len]).map_err(|_| bad("bad hash"))?;
+            let flags = be16(&data[start+40+hash_len..]);
+            pos = start + 42 + hash_len;
+
+            let extended = if flags & 0x4000 != 0 {
+                if version < 3 { return Err(bad("extended flags in a version 2 index")); }
+                if pos + 2 > end { return Err(bad("truncated entry")); }
+                pos += 2;
+                be16(&data[pos-2..])
+            } else {
+                0
+            };
+
+            let path = if version == 4 {
+                let (strip, n) = varint(&data[pos..end]).ok_or_else(|| bad("bad path prefix"))?;
+                pos += n;
+                if strip > prev_path.len() { return Err(bad("bad path prefix")); }
+                let nul = data[pos..end].iter().position(|b| *b == 0).ok_or_else(|| bad("unterminated path"))?;
+                let mut path = prev_path[..prev_path.len() - strip].to_vec();
+                path.extend_from_slice(&data[pos .. pos+nul]);
+                pos += nul + 1;
+                path
+            } else {
+                let nul = data[pos..end].iter().position(|b| *b == 0).ok_or_else(|| bad("unterminated path"))?;
+                let path = data[pos .. pos+nul].to_vec();
+                pos = start + ((pos - start + nul + 8) & !7); // 1 ..= 8 NULs pad entries to a multiple of 8 bytes
+                if pos > end { return Err(bad("truncated entry")); }
+                path
+            };
+
+            let entry = IndexEntry {
+                path:           Name::from(&path[..]),
+                hash,
+                mode,
+                stage:          ((flags >> 12) & 0x3) as u8,
+                assume_valid:   flags & 0x8000 != 0,
+                skip_worktree:  extended & 0x4000 != 0,
+                intent_to_add:  extended & 0x2000 != 0,
+                stat,
+                _nonexhaustive: (),
+            };
+            index.entries.entry(entry.path.clone()).or_default().push(entry);
+            prev_path = path;
+        }
+
+        while pos + 8 <= end {
+            let signature = &data[pos .. pos+4];
+            let size = be32(&data[pos+4..]) as usize;
+            pos += 8;
+            if pos + size > end { return Err(bad("truncated extension")); }
+            let ext = &data[pos .. pos+size];
+            pos += size;
+
+            match signature {
+                b"TREE" => index.cached_trees = parse_cached_trees(ext, hash_len).ok_or_else(|| bad("bad TREE extension"))?,
+                b"sdir" => {}, // sparse index: directory entries are already handled like files
+                b"link" => return Err(io::Error::new(io::ErrorKind::Unsupported, "split git indexes aren't supported")),
+                s if s[0].is_ascii_uppercase() => {}, // optional extension
+                _ => return Err(io::Error::new(io::ErrorKind::Unsupported, format!("unsupported required git index extension {:?}", String::from_utf8_lossy(signature)))),
+            }
+        }
+
+        for stages in index.entries.values_mut() { stages.sort_by_key(|e| e.stage); }
+        Ok(index)
+    }
+
+    /// The stage 0 (non-conflicted) entry for `path`, if any
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository  = Repository::from_path(".").unwrap();
+    /// let index       = repository.index().unwrap();
+    /// let cargo_toml  = index.entry("Cargo.toml").unwrap();
+    /// assert_eq!(cargo_toml.mode, tree::Mode::Regular);
+    /// assert_eq!(cargo_toml.stage, 0);
+    /// ```
+    pub fn entry(&self, path: impl AsRef<[u8]>) -> Option<&IndexEntry> {
+        self.entries.get(&Name::from(path.as_ref()))?.iter().find(|e| e.stage == 0)
+    }
+
+    /// Every path with merge conflicts, and it's stage 1 ..= 3 entries
+    pub fn conflicts(&self) -> impl Iterator<Item = (&Name, &[IndexEntry])> {
+        self.entries.iter().filter(|(_, stages)| stages.iter().any(|e| e.stage != 0)).map(|(path, stages)| (path, &stages[..]))
+    }
+
+    pub(crate) fn empty() -> Self {
+        Self { version: 2, entries: BTreeMap::new(), cached_trees: Vec::new(), _nonexhaustive: () }
+    }
+
+    /// Parse the output of `git ls-files --stage -z`.  Stat data and flags aren't available, and are left zeroed.
+    pub(crate) fn parse_ls_files_stage(output: &[u8]) -> io::Result<Self> {
+        let mut index = Self { version: 0, ..Self::empty() };
+        for record in output.split(|b| *b == 0).filter(|r| !r.is_empty()) {
+            let bad = || io::Error::new(io::ErrorKind::InvalidData, format!("git ls-files --stage returned unexpected record {:?}", String::from_utf8_lossy(record)));
+            let tab = record.iter().position(|b| *b == b'\t').ok_or_else(bad)?;
+            let mut fields = record[..tab].split(|b| *b == b' ');
+            let mode    = fields.next().and_then(tree::Mode::parse).ok_or_else(bad)?;
+            let hash    = fields.next().and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| blob::Hash::from_str(h).ok()).ok_or_else(bad)?;
+            let stage   = fields.next().and_then(|s| std::str::from_utf8(s).ok()).and_then(|s| s.parse().ok()).ok_or_else(bad)?;
+            let path    = Name::from(&record[tab+1..]);
+            let entry   = IndexEntry { path: path.clone(), hash, mode, stage, assume_valid: false, skip_worktree: false, intent_to_add: false, stat: IndexStat::default(), _nonexhaustive: () };
+            index.entries.entry(path).or_default().push(entry);
+        }
+        Ok(index)
+    }
+}
+
+
+
+fn be32(b: &[u8]) -> u32 { (u32::from(b[0]) << 24) | (u32::from(b[1]) << 16) | (u32::from(b[2]) << 8) | u32::from(b[3]) }
+fn be16(b: &[u8]) -> u16 { (u16::from(b[0]) << 8) | u16::from(b[1]) }
+
+/// Decode git's "offset" varint, returning (value, bytes consumed)
+fn varint(data: &[u8]) -> Option<(usize, usize)> {
+    let mut i = 0;
+    let mut c = *data.get(i)?;
+    let mut value = usize::from(c & 0x7F);
+    while c & 0x80 != 0 {
+        i += 1;
+        c = *data.get(i)?;
+        value = value.checked_add(1)?.checked_mul(128)? + usize::from(c & 0x7F);
+    }
+    Some((value, i + 1))
+}
+
+fn parse_cached_trees(mut ext: &[u8], hash_len: usize) -> Option<Vec<CachedTree>> {
+    // Entries are listed depth first, with paths relative to their parent: rebuild full paths with a stack of (path, remaining subtrees)
+    let mut trees = Vec::new();
+    let mut stack : Vec<(Vec<u8>, usize)> = Vec::new();
+    while !ext.is_empty() {
+        let nul = ext.iter().position(|b| *b == 0)?;
+        let name = &ext[..nul];
+        ext = &ext[nul+1..];
+        let nl = ext.iter().position(|b| *b == b'\n')?;
+        let counts = std::str::from_utf8(&ext[..nl]).ok()?;
+        ext = &ext[nl+1..];
+        let mut counts = counts.split(' ');
+        let entries : isize = counts.next()?.parse().ok()?;
+        let subtrees : usize = counts.next()?.parse().ok()?;
+
+        while let Some((_, 0)) = stack.last() { stack.pop(); }
+        let mut path = match stack.last_mut() {
+            Some((parent, remaining)) => {
+                *remaining -= 1;
+                let mut path = parent.clone();
+                if !path.is_empty() { path.push(b'/'); }
+                path
+            },
+            None => Vec::new(),
+        };
+        path.extend_from_slice(name);
+
+        if entries >= 0 { // -1 = invalidated, no hash follows
+            if ext.len() < hash_len { return None; }
+            let hash = tree::Hash::from_bytes(&ext[..hash_len]).ok()?;
+            ext = &ext[hash_len..];
+            trees.push(CachedTree { path: Name::from(&path[..]), entries: entries as usize, subtrees, hash, _nonexhaustive: () });
+        }
+        stack.push((path, subtrees));
+    }
+    Some(trees)
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    /// Serialize an index of (path, hash byte, flags, extended flags) entries, with an optional TREE extension
+    fn serialize(version: u32, hash_len: usize, entries: &[(&str, u8, u16, u16)], tree: Option<&[u8]>) -> Vec<u8> {
+        let mut data = b"DIRC".to_vec();
+        data.extend_from_slice(&version.to_be_bytes());
+        data.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        let mut prev = "";
+        for &(path, hash, flags, extended) in entries.iter() {
+            let start = data.len();
+            for stat in [1u32, 2, 3, 4, 5, 6, 0o100644, 7, 8, 9].iter() { data.extend_from_slice(&stat.to_be_bytes()); }
+            data.resize(data.len() + hash_len, hash);
+            let flags = flags | if extended != 0 { 0x4000 } else { 0 } | path.len().min(0xFFF) as u16;
+            data.extend_from_slice(&flags.to_be_bytes());
+            if extended != 0 { data.extend_from_slice(&extended.to_be_bytes()); }
+            if version == 4 {
+                let common = prev.bytes().zip(path.bytes()).take_while(|(a, b)| a == b).count();
+                data.push((prev.len() - common) as u8); // < 0x80: a single varint byte
+                data.extend_from_slice(&path.as_bytes()[common..]);
+                data.push(0);
+            } else {
+                data.extend_from_slice(path.as_bytes());
+                data.push(0);
+                data.resize(start + ((data.len() - start + 7) & !7), 0); // 1 ..= 8 NULs, counting the terminator above
+            }
+            prev = path;
+        }
+        if let Some(tree) = tree {
+            data.extend_from_slice(b"TREE");
+            data.extend_from_slice(&(tree.len() as u32).to_be_bytes());
+            data.extend_from_slice(tree);
+        }
+        data.resize(data.len() + hash_len, 0xCC); // checksum
+        data
+    }
+
+    #[test] fn parse_v2() {
+        let entries = [("a/b", 0x11, 0, 0), ("a/c", 0x22, 0x8000, 0), ("conflict", 0x33, 0x3000, 0), ("conflict", 0x44, 0x1000, 0), ("conflict", 0x55, 0x2000, 0)];
+        let index = Index::parse(&serialize(2, 20, &entries, None)).unwrap();
+        assert_eq!(index.version, 2);
+        assert_eq!(index.entries.len(), 3);
+
+        let ab = index.entry("a/b").unwrap();
+        assert_eq!(ab.hash.to_string(), "11".repeat(20));
+        assert_eq!(ab.mode,             tree::Mode::Regular);
+        assert_eq!(ab.stat,             IndexStat { ctime: (1, 2), mtime: (3, 4), dev: 5, ino: 6, uid: 7, gid: 8, size: 9 });
+        assert!(!ab.assume_valid);
+        assert!(index.entry("a/c").unwrap().assume_valid);
+
+        assert!(index.entry("conflict").is_none());
+        let conflicts = index.conflicts().collect::<Vec<_>>();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].1.iter().map(|e| e.stage).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(conflicts[0].1[0].hash.to_string(), "44".repeat(20));
+    }
+
+    #[test] fn parse_v3_extended_flags() {
+        let entries = [("skipped", 0x11, 0, 0x4000), ("intended", 0x22, 0, 0x2000), ("plain", 0x33, 0, 0)];
+        let index = Index::parse(&serialize(3, 20, &entries, None)).unwrap();
+        assert_eq!(index.version, 3);
+        assert!( index.entry("skipped").unwrap().skip_worktree);
+        assert!(!index.entry("skipped").unwrap().intent_to_add);
+        assert!( index.entry("intended").unwrap().intent_to_add);
+        assert!(!index.entry("plain").unwrap().skip_worktree);
+
+        assert_eq!(Index::parse(&serialize(2, 20, &entries, None)).unwrap_err().kind(), io::ErrorKind::InvalidData); // extended flags need v3
+    }
+
+    #[test] fn parse_v4_prefix_compression() {
+        let entries = [("src/lib.rs", 0x11, 0, 0), ("src/main.rs", 0x22, 0, 0), ("src/x/y.rs", 0x33, 0, 0), ("zzz", 0x44, 0, 0)];
+        let index = Index::parse(&serialize(4, 20, &entries, None)).unwrap();
+        assert_eq!(index.version, 4);
+        assert_eq!(index.entries.keys().map(|k| k.as_str_lossy().to_string()).collect::<Vec<_>>(), vec!["src/lib.rs", "src/main.rs", "src/x/y.rs", "zzz"]);
+        assert_eq!(index.entry("src/x/y.rs").unwrap().hash.to_string(), "33".repeat(20));
+    }
+
+    #[test] fn parse_cached_tree_extension() {
+        let mut tree = b"\x003 1\n".to_vec();
+        tree.extend_from_slice(&[0xAA; 20]);
+        tree.extend_from_slice(b"a\x00-1 1\n");             // invalidated: no hash
+        tree.extend_from_slice(b"b\x002 0\n");
+        tree.extend_from_slice(&[0xBB; 20]);
+        let entries = [("a/b/x", 0x11, 0, 0), ("a/b/y", 0x22, 0, 0), ("c", 0x33, 0, 0)];
+        let index = Index::parse(&serialize(2, 20, &entries, Some(&tree))).unwrap();
+        let trees = index.cached_trees.iter().map(|t| (t.path.as_str_lossy().to_string(), t.entries, t.subtrees, t.hash.to_string())).collect::<Vec<_>>();
+        assert_eq!(trees, vec![("".to_string(), 3, 1, "aa".repeat(20)), ("a/b".to_string(), 2, 0, "bb".repeat(20))]);
+    }
+
+    #[test] fn parse_sha256() {
+        let mut tree = b"\x001 0\n".to_vec();
+        tree.extend_from_slice(&[0xAA; 32]);
+        for &version in [2, 3, 4].iter() {
+            let index = Index::parse_sha256(&serialize(version, 32, &[("file", 0x11, 0, 0)], Some(&tree))).unwrap();
+            assert_eq!(index.entry("file").unwrap().hash.to_string(), "11".repeat(32));
+            assert_eq!(index.cached_trees[0].hash.to_string(), "aa".repeat(32));
+        }
+    }
+
+    #[test] fn parse_malformed() {
+        let kind = |data: &[u8]| Index::parse(data).unwrap_err().kind();
+        let good = serialize(2, 20, &[("file", 0x11, 0, 0)], None);
+        assert_eq!(kind(&good[..good.len()-30]),    io::ErrorKind::InvalidData);    // truncated
+        assert_eq!(kind(b"DIRX\0\0\0\x02\0\0\0\0"), io::ErrorKind::InvalidData);
+        let mut v5 = good.clone();
+        v5[7] = 5;
+        assert_eq!(kind(&v5),                       io::ErrorKind::Unsupported);
+
+        let mut split = good[..good.len()-20].to_vec();
+        split.extend_from_slice(b"link\0\0\0\0");
+        split.extend_from_slice(&[0xCC; 20]);
+        assert_eq!(kind(&split),                    io::ErrorKind::Unsupported);
+
+        let mut required = good[..good.len()-20].to_vec();
+        required.extend_from_slice(b"zzzz\0\0\0\0");
+        required.extend_from_slice(&[0xCC; 20]);
+        assert_eq!(kind(&required),                 io::ErrorKind::Unsupported);
+    }
+
+    #[test] fn parse_ls_files() {
+        let output = b"100644 1111111111111111111111111111111111111111 0\tsp ace\x00160000 2222222222222222222222222222222222222222 0\tsub\0";
+        let index = Index::parse_ls_files_stage(output).unwrap();
+        assert_eq!(index.version, 0);
+        assert_eq!(index.entry("sp ace").unwrap().mode, tree::Mode::Regular);
+        assert_eq!(index.entry("sub").unwrap().mode,    tree::Mode::Gitlink);
+        assert!(Index::parse_ls_files_stage(b"100644 nothex 0\tx\0").is_err());
+    }
+}
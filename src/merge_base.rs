@@ -0,0 +1,149 @@
+use crate::*;
+
+use std::cmp::Reverse;
+use std::collections::*;
+use std::io;
+
+
+
+const PARENT1   : u8 = 0x01;
+const PARENT2   : u8 = 0x02;
+const STALE     : u8 = 0x04;
+const RESULT    : u8 = 0x08;
+
+/// Paint ancestors of `one` with PARENT1 and ancestors of `twos` with PARENT2, in commit date order, until only commits
+/// reachable from common ancestors remain.  Returns the common ancestors found (some possibly redundant) and every commit's flags.
+///
+/// This is `git`'s `paint_down_to_common`.
+fn paint_down_to_common(cache: &RepositoryCache, one: &commit::Hash, twos: &[commit::Hash]) -> io::Result<(Vec<commit::Hash>, HashMap<commit::Hash, u8>)> {
+    let mut painter = Painter::default();
+    let mut results = Vec::new();
+
+    painter.paint(cache, one, PARENT1)?;
+    for two in twos { painter.paint(cache, two, PARENT2)?; }
+
+    while painter.non_stale > 0 {
+        let hash = painter.pop();
+        let mut inherit = painter.flags[&hash] & (PARENT1 | PARENT2 | STALE);
+        if inherit == PARENT1 | PARENT2 {
+            let f = painter.flags.get_mut(&hash).unwrap();
+            if *f & RESULT == 0 {
+                *f |= RESULT;
+                results.push(hash.clone());
+            }
+            inherit |= STALE; // everything below a common ancestor is a worse common ancestor
+        }
+
+        for parent in cache.commit(&hash)?.parents.iter() {
+            painter.paint(cache, parent, inherit)?;
+        }
+    }
+
+    let flags = painter.flags;
+    results.retain(|hash| flags[hash] & STALE == 0);
+    Ok((results, flags))
+}
+
+/// The commit date ordered queue and flags of [paint_down_to_common]
+#[derive(Default)]
+struct Painter {
+    flags:      HashMap<commit::Hash, u8>,
+    heap:       BinaryHeap<(i64, Reverse<usize>, commit::Hash)>,
+    queued:     HashSet<commit::Hash>,
+    seq:        usize,
+    /// How many commits in `heap` aren't STALE (once none are, painting is done)
+    non_stale:  usize,
+}
+
+impl Painter {
+    /// Add `flags` to `hash`, (re)queueing it if they're new
+    fn paint(&mut self, cache: &RepositoryCache, hash: &commit::Hash, flags: u8) -> io::Result<()> {
+        let f = self.flags.entry(hash.clone()).or_insert(0);
+        let old = *f;
+        if old & flags == flags { return Ok(()); }
+        *f |= flags;
+
+        if self.queued.contains(hash) {
+            if old & STALE == 0 && flags & STALE != 0 { self.non_stale -= 1; }
+        } else {
+            if flags & STALE == 0 && old & STALE == 0 { self.non_stale += 1; }
+            self.seq += 1;
+            self.heap.push((cache.commit(hash)?.committer.time, Reverse(self.seq), hash.clone()));
+            self.queued.insert(hash.clone());
+        }
+        Ok(())
+    }
+
+    fn pop(&mut self) -> commit::Hash {
+        let (_, _, hash) = self.heap.pop().unwrap();
+        self.queued.remove(&hash);
+        if self.flags[&hash] & STALE == 0 { self.non_stale -= 1; }
+        hash
+    }
+}
+
+/// Sort newest commit date first, dropping duplicates
+fn sort_by_date(cache: &RepositoryCache, commits: &mut Vec<commit::Hash>) -> io::Result<()> {
+    let mut dated = Vec::with_capacity(commits.len());
+    let mut seen = HashSet::new();
+    for (i, hash) in commits.drain(..).enumerate() {
+        if !seen.insert(hash.clone()) { continue; }
+        dated.push((Reverse(cache.commit(&hash)?.committer.time), i, hash));
+    }
+    dated.sort();
+    commits.extend(dated.into_iter().map(|(_, _, hash)| hash));
+    Ok(())
+}
+
+
+
+pub(crate) fn merge_bases(cache: &RepositoryCache, one: &commit::Hash, twos: &[commit::Hash]) -> io::Result<Vec<commit::Hash>> {
+    if twos.contains(one) { return Ok(vec![one.clone()]); }
+    let (bases, _) = paint_down_to_common(cache, one, twos)?;
+    if bases.len() <= 1 { return Ok(bases); }
+    independent(cache, &bases)
+}
+
+pub(crate) fn octopus_merge_bases(cache: &RepositoryCache, commits: &[commit::Hash]) -> io::Result<Vec<commit::Hash>> {
+    let mut bases = match commits.first() { Some(first) => vec![first.clone()], None => return Ok(Vec::new()) };
+    for next in commits[1..].iter() {
+        let mut new_bases = Vec::new();
+        for base in bases.iter() {
+            new_bases.extend(merge_bases(cache, base, std::slice::from_ref(next))?);
+        }
+        bases = independent(cache, &new_bases)?; // a base of one pair can be an ancestor of another's
+    }
+    Ok(bases)
+}
+
+pub(crate) fn is_ancestor(cache: &RepositoryCache, ancestor: &commit::Hash, descendant: &commit::Hash) -> io::Result<bool> {
+    if ancestor == descendant { return Ok(true); }
+    let (_, flags) = paint_down_to_common(cache, ancestor, std::slice::from_ref(descendant))?;
+    Ok(flags[ancestor] & PARENT2 != 0)
+}
+
+/// Drop any of `commits` reachable from another, like git's `remove_redundant`: one paint per remaining commit, rather than comparing every pair
+pub(crate) fn independent(cache: &RepositoryCache, commits: &[commit::Hash]) -> io::Result<Vec<commit::Hash>> {
+    let mut commits = commits.to_vec();
+    sort_by_date(cache, &mut commits)?;
+    let mut redundant = vec![false; commits.len()];
+    for i in 0 .. commits.len() {
+        if redundant[i] { continue; }
+        let others = (0 .. commits.len()).filter(|&j| j != i && !redundant[j]).collect::<Vec<_>>();
+        if others.is_empty() { break; }
+        let (_, flags) = paint_down_to_common(cache, &commits[i], &others.iter().map(|&j| commits[j].clone()).collect::<Vec<_>>())?;
+        if flags[&commits[i]] & PARENT2 != 0 { redundant[i] = true; }
+        for j in others {
+            if flags.get(&commits[j]).cloned().unwrap_or(0) & PARENT1 != 0 { redundant[j] = true; }
+        }
+    }
+    Ok(commits.into_iter().zip(redundant).filter(|(_, redundant)| !redundant).map(|(commit, _)| commit).collect())
+}
+
+/// Count the commits reachable from only `a`, and only `b`, with a single paint
+pub(crate) fn ahead_behind(cache: &RepositoryCache, a: &commit::Hash, b: &commit::Hash) -> io::Result<(usize, usize)> {
+    let (_, flags) = paint_down_to_common(cache, a, std::slice::from_ref(b))?;
+    let ahead   = flags.values().filter(|&&f| f & (PARENT1 | PARENT2) == PARENT1).count();
+    let behind  = flags.values().filter(|&&f| f & (PARENT1 | PARENT2) == PARENT2).count();
+    Ok((ahead, behind))
+}
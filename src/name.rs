@@ -48,6 +48,7 @@ impl Default            for Name { fn default() -> Self { Self(Int::UTF8(String:
 impl From<String>       for Name { fn from(v: String) -> Self { Name(Int::UTF8(v)) } }
 impl From<&str>         for Name { fn from(v: &str) -> Self { Name(Int::UTF8(v.to_owned())) } }
 impl Borrow<str>        for Name { fn borrow(&self) -> &str { self.as_str_lossy() } }
+impl AsRef<[u8]>        for Name { fn as_ref(&self) -> &[u8] { self.as_bytes().unwrap_or_else(|| self.as_str_lossy().as_bytes()) } }
 
 impl PartialEq<str>     for Name { fn eq(&self, other: &str     ) -> bool { self.as_str() == Some(other) } }
 impl PartialEq<&str>    for Name { fn eq(&self, other: &&str    ) -> bool { self.as_str() == Some(*other) } }
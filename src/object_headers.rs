@@ -0,0 +1,29 @@
+/// (key, value) pairs of a raw commit or tag object
+pub(crate) type ObjectHeaders = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// Split a raw commit or tag object into it's headers and message.
+///
+/// Header values spanning multiple lines (e.g. "gpgsig", "mergetag") have their continuation lines' leading space stripped,
+/// and are joined with "\n".
+pub(crate) fn parse_object_headers(data: &[u8]) -> (ObjectHeaders, &[u8]) {
+    let mut headers = ObjectHeaders::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let eol = rest.iter().position(|b| *b == b'\n').unwrap_or(rest.len());
+        let line = &rest[..eol];
+        rest = &rest[(eol+1).min(rest.len())..];
+        if line.is_empty() { break } // message follows
+
+        if line[0] == b' ' {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(b'\n');
+                value.extend_from_slice(&line[1..]);
+                continue;
+            }
+        }
+
+        let sp = line.iter().position(|b| *b == b' ').unwrap_or(line.len());
+        headers.push((line[..sp].to_vec(), line[(sp+1).min(line.len())..].to_vec()));
+    }
+    (headers, rest)
+}
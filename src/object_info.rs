@@ -0,0 +1,157 @@
+use crate::*;
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::ChildStdout;
+
+
+
+/// Metadata about a git object, as reported by `git cat-file --batch-check`
+#[derive(Clone, Debug)]
+pub struct ObjectInfo {
+    /// The [Hash](unknown::Hash) of the object
+    pub hash:           unknown::Hash,
+
+    /// The type of the object (blob, commit, tree, ...)
+    pub file_type:      FileType,
+
+    /// The uncompressed size of the object, in bytes
+    pub size:           u64,
+
+    /// The on-disk size of the object, in bytes (`%(objectsize:disk)`), if requested via [ObjectInfoFields::disk_size]
+    pub disk_size:      Option<u64>,
+
+    /// The object this object is stored as a delta against (`%(deltabase)`), if requested via [ObjectInfoFields::delta_base] and stored as a delta
+    pub delta_base:     Option<unknown::Hash>,
+
+    _nonexhaustive:     (),
+}
+
+/// Optional [ObjectInfo] fields to request from `git cat-file --batch-check`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ObjectInfoFields {
+    /// Request [ObjectInfo::disk_size] (`%(objectsize:disk)`)
+    pub disk_size:      bool,
+
+    /// Request [ObjectInfo::delta_base] (`%(deltabase)`)
+    pub delta_base:     bool,
+}
+
+impl ObjectInfoFields {
+    pub(crate) fn batch_check_arg(&self) -> String {
+        let mut arg = String::from("--batch-check=%(objectname) %(objecttype) %(objectsize)");
+        if self.disk_size   { arg.push_str(" %(objectsize:disk)"); }
+        if self.delta_base  { arg.push_str(" %(deltabase)"); }
+        arg
+    }
+}
+
+
+
+/// Streams [ObjectInfo]s out of a `git cat-file --batch-check` process
+pub(crate) struct ObjectInfos {
+    pub(crate) child:   GitChild,
+    pub(crate) stdout:  BufReader<ChildStdout>,
+    pub(crate) fields:  ObjectInfoFields,
+    pub(crate) done:    bool,
+}
+
+impl ObjectInfos {
+    /// Feed `hashes` to `child`'s stdin from a background thread, so we can read stdout without deadlocking.
+    ///
+    /// The thread is detached rather than joined, since `hashes` may block or never end: once git is gone, it's next write fails and it exits.
+    pub(crate) fn new(mut child: GitChild, hashes: Option<Box<dyn Iterator<Item = unknown::Hash> + Send>>, fields: ObjectInfoFields) -> Self {
+        if let (Some(mut stdin), Some(hashes)) = (child.child.stdin.take(), hashes) {
+            std::thread::spawn(move || {
+                for hash in hashes {
+                    if stdin.write_all(HashTempStr::new(&hash).bytes()).and_then(|_| stdin.write_all(b"\n")).is_err() { break } // git died - reported on wait()
+                }
+            });
+        }
+        let stdout = BufReader::new(child.child.stdout.take().unwrap());
+        Self { child, stdout, fields, done: false }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.done = true;
+        Ok(self.child.wait()?)
+    }
+
+    fn parse(&self, line: &str) -> io::Result<ObjectInfo> {
+        let bad_line = || io::Error::new(io::ErrorKind::InvalidData, format!("git cat-file --batch-check returned unexpected line {:?}", line));
+        let mut fields = line.split(' ');
+
+        let hash = fields.next().ok_or_else(bad_line)?;
+        let ty   = fields.next().ok_or_else(bad_line)?;
+        let hash = unknown::Hash::from_str(hash).map_err(GitError::from)?;
+        if ty == "missing" { return Err(GitError::ObjectNotFound(hash).into()); }
+        let size = fields.next().and_then(|s| s.parse().ok()).ok_or_else(bad_line)?;
+
+        let disk_size = if self.fields.disk_size {
+            Some(fields.next().and_then(|s| s.parse().ok()).ok_or_else(bad_line)?)
+        } else {
+            None
+        };
+
+        let delta_base = if self.fields.delta_base {
+            let base = unknown::Hash::from_str(fields.next().ok_or_else(bad_line)?).map_err(GitError::from)?;
+            if base.bytes().iter().all(|b| *b == 0) { None } else { Some(base) }
+        } else {
+            None
+        };
+
+        if fields.next().is_some() { return Err(bad_line()); }
+
+        Ok(ObjectInfo {
+            hash,
+            file_type: FileType::from(ty),
+            size,
+            disk_size,
+            delta_base,
+            _nonexhaustive: (),
+        })
+    }
+}
+
+impl Iterator for ObjectInfos {
+    type Item = io::Result<ObjectInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None; }
+
+        let mut line = String::new();
+        match self.stdout.read_line(&mut line) {
+            Ok(0)   => self.finish().err().map(Err),
+            Ok(_)   => Some(self.parse(line.trim_end_matches('\n'))),
+            Err(e)  => { let _ = self.child.kill(); let _ = self.finish(); Some(Err(e)) },
+        }
+    }
+}
+
+impl Drop for ObjectInfos {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.child.kill();
+            let _ = self.finish();
+        }
+    }
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    #[test] fn drop_early() {
+        let repository  = Repository::from_path(".").unwrap();
+        let head        = repository.rev_parse("HEAD").unwrap();
+
+        let endless = repository.cat_file_batch_check(std::iter::repeat(head.clone()), Default::default()).unwrap();
+        for info in endless.take(3) { assert_eq!(info.unwrap().file_type, FileType::Commit); }
+
+        let (send, recv) = std::sync::mpsc::channel();
+        send.send(head).unwrap();
+        let blocked = repository.cat_file_batch_check(recv, Default::default()).unwrap();
+        drop(blocked); // mustn't wait for `send` to hang up
+        drop(send);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::*;
+
+use std::collections::*;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+
+
+/// A parsed `.git/packed-refs` file, as written by `git pack-refs` / `git gc`
+#[derive(Default)]
+pub(crate) struct PackedRefs {
+    /// "peeled" trait: annotated tags under refs/tags/ are followed by a "^\<peeled hash\>" line
+    pub peeled:         bool,
+
+    /// "fully-peeled" trait: *every* peelable ref is followed by a "^\<peeled hash\>" line
+    pub fully_peeled:   bool,
+
+    /// Full ref names (e.g. "refs/heads/master") => [PackedRef]s
+    pub refs:           BTreeMap<OsString, PackedRef>,
+}
+
+pub(crate) struct PackedRef {
+    pub hash:   unknown::Hash,
+    pub peeled: Option<unknown::Hash>,
+}
+
+impl PackedRefs {
+    /// Read `dot_git/packed-refs`.  A missing file is treated as empty.
+    pub fn read(dot_git: &Path) -> io::Result<Self> {
+        let text = match std::fs::read(dot_git.join("packed-refs")) {
+            Ok(text)    => text,
+            Err(ref e)  if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e)      => return Err(e),
+        };
+        Self::parse(&text)
+    }
+
+    fn parse(text: &[u8]) -> io::Result<Self> {
+        let mut packed = Self::default();
+        let mut last : Option<OsString> = None;
+        let bad_line = |line: &[u8]| io::Error::new(io::ErrorKind::InvalidData, format!("packed-refs: unexpected line {:?}", String::from_utf8_lossy(line)));
+        let hash = |hex: &[u8]| -> io::Result<unknown::Hash> { Ok(unknown::Hash::from_str(std::str::from_utf8(hex).map_err(|_| bad_line(hex))?)?) };
+
+        for line in text.split(|b| *b == b'\n') {
+            let line = if line.ends_with(b"\r") { &line[..line.len()-1] } else { line };
+            if line.starts_with(HEADER) {
+                for t in line[HEADER.len()..].split(|b| *b == b' ') {
+                    match t {
+                        b"peeled"       => packed.peeled = true,
+                        b"fully-peeled" => packed.fully_peeled = true,
+                        _other          => {}, // "sorted", ...
+                    }
+                }
+            } else if line.is_empty() || line[0] == b'#' {
+                // comment
+            } else if line[0] == b'^' {
+                let peeled = hash(&line[1..])?;
+                let prev = last.as_ref().and_then(|name| packed.refs.get_mut(name));
+                let prev = prev.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "packed-refs: peeled line without a preceeding ref"))?;
+                prev.peeled = Some(peeled);
+            } else {
+                let sp = line.iter().position(|b| *b == b' ').ok_or_else(|| bad_line(line))?;
+                let name = os_string(&line[sp+1..]);
+                packed.refs.insert(name.clone(), PackedRef { hash: hash(&line[..sp])?, peeled: None });
+                last = Some(name);
+            }
+        }
+
+        Ok(packed)
+    }
+
+    /// Whether a ref without a "^" line is known not to be an annotated tag
+    pub fn is_peeled(&self, name: &str) -> bool {
+        self.fully_peeled || (self.peeled && name.starts_with("refs/tags/"))
+    }
+
+    /// Refs starting with `prefix` (e.g. "refs/heads/"), with said prefix stripped from their names
+    pub fn prefixed<'s>(&'s self, prefix: &'s str) -> impl Iterator<Item = (OsString, &'s PackedRef)> + 's {
+        self.refs.iter().filter_map(move |(name, r)| {
+            let name = os_bytes(name)?;
+            if name.starts_with(prefix.as_bytes()) { Some((os_string(&name[prefix.len()..]), r)) } else { None }
+        })
+    }
+}
+
+
+
+/// The last [PackedRefs] read, reused until `packed-refs` is rewritten (as judged by it's size and modification time, like git does)
+#[derive(Default)]
+pub(crate) struct PackedRefsCache(Mutex<Option<(Stamp, Arc<PackedRefs>)>>);
+
+/// `packed-refs`' (modification time, size), or `None` if it doesn't exist
+type Stamp = Option<(SystemTime, u64)>;
+
+impl PackedRefsCache {
+    pub fn get(&self, dot_git: &Path) -> io::Result<Arc<PackedRefs>> {
+        let stamp = match std::fs::metadata(dot_git.join("packed-refs")) {
+            Ok(meta)    => Some((meta.modified()?, meta.len())),
+            Err(ref e)  if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e)      => return Err(e),
+        };
+
+        let mut cache = self.0.lock().unwrap();
+        if let Some((cached_stamp, ref packed)) = *cache {
+            if cached_stamp == stamp { return Ok(packed.clone()); }
+        }
+        let packed = Arc::new(PackedRefs::read(dot_git)?);
+        *cache = Some((stamp, packed.clone()));
+        Ok(packed)
+    }
+}
+
+
+
+const HEADER : &[u8] = b"# pack-refs with:";
+
+#[cfg(unix)] fn os_bytes(s: &OsStr) -> Option<&[u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Some(s.as_bytes())
+}
+
+#[cfg(not(unix))] fn os_bytes(s: &OsStr) -> Option<&[u8]> {
+    s.to_str().map(str::as_bytes)
+}
+
+#[cfg(unix)] pub(crate) fn os_string(bytes: &[u8]) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(bytes.to_vec())
+}
+
+#[cfg(not(unix))] pub(crate) fn os_string(bytes: &[u8]) -> OsString {
+    String::from_utf8_lossy(bytes).into_owned().into() // ref names are UTF-8 in practice off unix
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    const A : &str = "0123456789012345678901234567890123456789";
+    const B : &str = "89abcdef89abcdef89abcdef89abcdef89abcdef";
+
+    #[test] fn parse_traits_and_peeled() {
+        let text = format!("# pack-refs with: peeled fully-peeled sorted \n{a} refs/heads/master\n{b} refs/tags/v1\n^{a}\n", a = A, b = B);
+        let packed = PackedRefs::parse(text.as_bytes()).unwrap();
+        assert!(packed.peeled && packed.fully_peeled);
+        assert_eq!(packed.refs.len(), 2);
+        assert_eq!(packed.refs[OsStr::new("refs/heads/master")].hash.to_string(), A);
+        assert!(packed.refs[OsStr::new("refs/heads/master")].peeled.is_none());
+        assert_eq!(packed.refs[OsStr::new("refs/tags/v1")].peeled.as_ref().map(|p| p.to_string()), Some(A.to_string()));
+    }
+
+    #[test] fn parse_crlf_and_blank_lines() {
+        let text = format!("# pack-refs with: peeled\r\n\r\n{} refs/heads/master\r\n", A);
+        let packed = PackedRefs::parse(text.as_bytes()).unwrap();
+        assert!(packed.peeled && !packed.fully_peeled);
+        assert!(packed.refs.contains_key(OsStr::new("refs/heads/master")));
+        assert!(PackedRefs::parse(b"").unwrap().refs.is_empty());
+    }
+
+    #[test] fn parse_malformed() {
+        assert_eq!(PackedRefs::parse(format!("^{}\n", A).as_bytes()).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(PackedRefs::parse(A.as_bytes()).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(PackedRefs::parse(b"not-a-hash refs/heads/master\n").err().unwrap().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(PackedRefs::parse(b"\xff\xfe refs/heads/master\n").err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(unix)] #[test] fn non_utf8_names() {
+        let mut text = format!("{} refs/heads/caf", A).into_bytes();
+        text.extend_from_slice(b"\xe9\n");
+        let packed = PackedRefs::parse(&text).unwrap();
+        let branches = packed.prefixed("refs/heads/").map(|(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(branches, vec![os_string(b"caf\xe9")]);
+        assert_eq!(packed.prefixed("refs/tags/").count(), 0);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::*;
+
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+
+
+/// Describes how resolving a path within a [Tree] (e.g. [RepositoryCache::tree_entry_at]) failed.
+/// Convertable to [std::io::Error], [Box]&lt;dyn [std::error::Error]&gt;.
+#[derive(Debug)]
+pub enum PathError {
+    /// The path (up to and including the [Name] that was missing) doesn't exist.  Empty paths are also reported as not found.
+    NotFound(Name),
+
+    /// The path (up to and including the [Name] given) is a Blob, but was treated as a directory
+    NotADirectory(Name),
+
+    /// The path (up to and including the [Name] given) is a submodule, whose contents live in another repository
+    CrossedSubmodule(Name),
+
+    /// Reading a [Commit] or [Tree] failed
+    Io(io::Error),
+}
+
+impl std::error::Error for PathError {}
+
+impl Display for PathError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            PathError::NotFound(path)           => write!(fmt, "Path {:?} not found", path),
+            PathError::NotADirectory(path)      => write!(fmt, "Path {:?} is not a directory", path),
+            PathError::CrossedSubmodule(path)   => write!(fmt, "Path {:?} is a submodule", path),
+            PathError::Io(e)                    => write!(fmt, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for PathError {
+    fn from(e: io::Error) -> Self { PathError::Io(e) }
+}
+
+impl From<PathError> for io::Error {
+    fn from(pe: PathError) -> Self {
+        match pe {
+            PathError::Io(e)                    => e,
+            PathError::NotFound(_)              => io::Error::new(io::ErrorKind::NotFound, pe),
+            PathError::NotADirectory(_)         => io::Error::new(io::ErrorKind::InvalidInput, pe),
+            PathError::CrossedSubmodule(_)      => io::Error::new(io::ErrorKind::InvalidInput, pe),
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::*;
+
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+
+
+
+/// A loose or packed ref (e.g. "refs/heads/master", "refs/notes/commits", "refs/pull/123/head"), as enumerated by [Repository::refs]
+pub struct Reference {
+    pub(crate) name:    OsString,
+    pub(crate) target:  RefTarget,
+    pub(crate) peeled:  Option<unknown::Hash>,
+}
+
+impl Reference {
+    /// Full name of the ref (e.g. "refs/heads/master")
+    pub fn name(&self) -> &OsStr { &self.name }
+
+    /// What the ref points to, without following symbolic refs
+    pub fn target(&self) -> &RefTarget { &self.target }
+
+    /// The [Hash](unknown::Hash) this ref points to directly, unless it's a symbolic ref
+    pub fn hash(&self) -> Option<&unknown::Hash> {
+        match &self.target {
+            RefTarget::Direct(hash)     => Some(hash),
+            RefTarget::Symbolic(_)      => None,
+        }
+    }
+
+    /// The peeled object (e.g. the [Commit] an annotated [Tag] ultimately points to), if `packed-refs` recorded it
+    pub fn peeled(&self) -> Option<&unknown::Hash> { self.peeled.as_ref() }
+
+    /// `true` if this is a symbolic ref (e.g. "refs/remotes/origin/HEAD")
+    pub fn is_symbolic(&self) -> bool {
+        match &self.target {
+            RefTarget::Symbolic(_)      => true,
+            RefTarget::Direct(_)        => false,
+        }
+    }
+}
+
+impl Debug for Reference {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match (&self.target, self.peeled.as_ref()) {
+            (RefTarget::Direct(hash), Some(peeled)) => write!(fmt, "Reference({:?} => \"{}\" => \"{}\")", self.name, hash, peeled),
+            (RefTarget::Direct(hash), None)         => write!(fmt, "Reference({:?} => \"{}\")", self.name, hash),
+            (RefTarget::Symbolic(target), _)        => write!(fmt, "Reference({:?} => {:?})", self.name, target),
+        }
+    }
+}
+
+
+
+/// Reject ref names `git check-ref-format` would (e.g. "", "/etc/passwd", "../config", "refs//heads", "refs/heads/x.lock", "HEAD@{1}"),
+/// so they can be safely joined onto a git directory.  One-level names like "HEAD" and "FETCH_HEAD" are allowed.
+pub(crate) fn check_ref_format(name: &str) -> io::Result<()> {
+    let valid = !name.is_empty()
+        && name != "@"
+        && !name.ends_with('.')
+        && !name.contains("..")
+        && !name.contains("@{")
+        && !name.bytes().any(|b| b < 0x20 || b == 0x7f || b" ~^:?*[\\".contains(&b))
+        && name.split('/').all(|c| !c.is_empty() && !c.starts_with('.') && !c.ends_with(".lock"));
+    if valid { Ok(()) } else { Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid ref name {:?}", name))) }
+}
+
+/// Match a full ref name against a `git for-each-ref` style pattern:
+/// patterns without glob characters match literally or up to a slash ("refs/heads" matches "refs/heads/master"),
+/// otherwise `*`, `?`, and `[...]` classes (e.g. "[a-z]", "[!0-9]") match within a single path component, and `**` matches across components.
+pub(crate) fn ref_pattern_matches(pattern: &str, name: &str) -> bool {
+    if pattern.is_empty() { return true; }
+    if !pattern.contains(&['*', '?', '['][..]) {
+        return name == pattern || (name.starts_with(pattern) && (pattern.ends_with('/') || name[pattern.len()..].starts_with('/')));
+    }
+    glob(pattern.as_bytes(), name.as_bytes())
+}
+
+/// Iterative wildmatch: on a mismatch, retry with the most recent `*` consuming one more byte of `name`, or failing that
+/// (it can't consume a '/'), the most recent `**`.  Earlier stars never need revisiting, so this is O(pattern * name).
+fn glob(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut star    : Option<(usize, usize)> = None; // (pattern index after `*`,  name index it's matched up to)
+    let mut double  : Option<(usize, usize)> = None; // (pattern index after `**`, name index it's matched up to)
+
+    loop {
+        if p < pattern.len() {
+            let c = name.get(n).cloned();
+            match pattern[p] {
+                b'*' if pattern.get(p+1) == Some(&b'*') => {
+                    while pattern.get(p) == Some(&b'*') { p += 1; }
+                    double  = Some((p, n));
+                    star    = None;
+                    continue;
+                },
+                b'*' => {
+                    p += 1;
+                    star = Some((p, n));
+                    continue;
+                },
+                b'?' => if c.is_some() && c != Some(b'/') {
+                    p += 1;
+                    n += 1;
+                    continue;
+                },
+                b'[' => if let Some(c) = c {
+                    if let Some((true, len)) = class(&pattern[p..], c) {
+                        p += len;
+                        n += 1;
+                        continue;
+                    }
+                },
+                literal => if c == Some(literal) {
+                    p += 1;
+                    n += 1;
+                    continue;
+                },
+            }
+        } else if n == name.len() {
+            return true;
+        }
+
+        if let Some((sp, sn)) = star {
+            if sn < name.len() && name[sn] != b'/' {
+                star = Some((sp, sn+1));
+                p = sp;
+                n = sn+1;
+                continue;
+            }
+        }
+        if let Some((dp, dn)) = double {
+            if dn < name.len() {
+                double  = Some((dp, dn+1));
+                star    = None;
+                p = dp;
+                n = dn+1;
+                continue;
+            }
+        }
+        return false;
+    }
+}
+
+/// Match `c` against the `[...]` class at the start of `pattern`, returning (matched, length of the class), or `None` if it's unterminated
+fn class(pattern: &[u8], c: u8) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negate = match pattern.get(i) { Some(b'!') | Some(b'^') => { i += 1; true }, _ => false };
+    let first = i;
+    let mut matched = false;
+    loop {
+        let lo = *pattern.get(i)?;
+        if lo == b']' && i > first { break; } // a leading ']' is literal
+        match (pattern.get(i+1), pattern.get(i+2)) {
+            (Some(b'-'), Some(&hi)) if hi != b']' => {
+                if lo <= c && c <= hi { matched = true; }
+                i += 3;
+            },
+            _ => {
+                if lo == c { matched = true; }
+                i += 1;
+            },
+        }
+    }
+    Some((matched != negate && c != b'/', i+1))
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    #[test] fn check_ref_format_valid() {
+        for name in ["HEAD", "FETCH_HEAD", "refs/heads/master", "refs/heads/feature/x-1", "refs/tags/v1.0", "refs/heads/caf\u{e9}", "refs/heads/a@b"].iter() {
+            assert!(check_ref_format(name).is_ok(), "{:?} should be valid", name);
+        }
+    }
+
+    #[test] fn check_ref_format_invalid() {
+        for name in [
+            "", "@", "/etc/passwd", "../../etc/passwd", "refs/../config", "refs//heads", "refs/heads/", "refs/heads/.hidden", "refs/heads/x.lock",
+            "refs/heads/x.", "HEAD@{1}", "refs/heads/a b", "refs/heads/a~1", "refs/heads/a^", "refs/heads/a:b", "refs/heads/*", "refs/heads/[a]",
+            "refs\\heads\\master", "C:/refs", "refs/heads/\t", "refs/heads/\x7f",
+        ].iter() {
+            assert_eq!(check_ref_format(name).err().map(|e| e.kind()), Some(io::ErrorKind::InvalidInput), "{:?} should be invalid", name);
+        }
+    }
+
+    #[test] fn glob_patterns() {
+        let cases : &[(&str, &str, bool)] = &[
+            ("refs/heads/*",            "refs/heads/master",            true),
+            ("refs/heads/*",            "refs/heads/feature/x",         false),
+            ("refs/heads/**",           "refs/heads/feature/x",         true),
+            ("refs/**/HEAD",            "refs/remotes/origin/HEAD",     true),
+            ("refs/pull/*/head",        "refs/pull/123/head",           true),
+            ("refs/pull/*/head",        "refs/pull/1/2/head",           false),
+            ("refs/tags/v?.0",          "refs/tags/v1.0",               true),
+            ("refs/tags/v?.0",          "refs/tags/v10.0",              false),
+            ("refs/tags/v[0-9].*",      "refs/tags/v1.2",               true),
+            ("refs/tags/v[0-9].*",      "refs/tags/vx.2",               false),
+            ("refs/tags/v[!0-9]*",      "refs/tags/vx",                 true),
+            ("refs/tags/v[^0-9]*",      "refs/tags/v1",                 false),
+            ("refs/tags/[]a]",          "refs/tags/]",                  true),
+            ("refs/tags/[a-]",          "refs/tags/-",                  true),
+            ("refs/tags/[abc",          "refs/tags/a",                  false),
+            ("refs/*[/]x",              "refs/a/x",                     false),
+            ("refs/heads/a**b*c",       "refs/heads/a/b/bxc",           true),
+        ];
+        for &(pattern, name, expected) in cases {
+            assert_eq!(ref_pattern_matches(pattern, name), expected, "{:?} vs {:?}", pattern, name);
+        }
+    }
+
+    #[test] fn glob_pathological() {
+        let pattern = "refs/*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*b";
+        let name    = format!("refs/{}", "a".repeat(200));
+        assert!(!ref_pattern_matches(pattern, &name));
+        assert!(!ref_pattern_matches(&pattern.replace('*', "**"), &format!("refs/{}", "a/".repeat(100))));
+    }
+
+    #[test] fn glob_matches_backtracking_reference() {
+        fn reference(pattern: &[u8], name: &[u8]) -> bool {
+            match pattern.first() {
+                None                                        => name.is_empty(),
+                Some(b'*') if pattern.get(1) == Some(&b'*') => (0 ..= name.len()).any(|i| reference(&pattern[2..], &name[i..])),
+                Some(b'*')                                  => (0 ..= name.len()).take_while(|i| !name[..*i].contains(&b'/')).any(|i| reference(&pattern[1..], &name[i..])),
+                Some(b'?')                                  => !name.is_empty() && name[0] != b'/' && reference(&pattern[1..], &name[1..]),
+                Some(b'[')                                  => match (name.first(), name.first().and_then(|c| class(pattern, *c))) {
+                    (Some(_), Some((true, len)))    => reference(&pattern[len..], &name[1..]),
+                    _                               => false,
+                },
+                Some(c)                                     => name.first() == Some(c) && reference(&pattern[1..], &name[1..]),
+            }
+        }
+
+        let mut seed = 0x2545_f491_u32;
+        let mut random = |alphabet: &[u8], max: u32| -> Vec<u8> {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let len = (seed >> 16) % max;
+            (0..len).map(|_| { seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345); alphabet[(seed >> 16) as usize % alphabet.len()] }).collect()
+        };
+        for _ in 0..20000 {
+            let pattern = random(b"ab/*?[]!-", 8);
+            let name    = random(b"ab/-]", 8);
+            assert_eq!(glob(&pattern, &name), reference(&pattern, &name), "{:?} vs {:?}", String::from_utf8_lossy(&pattern), String::from_utf8_lossy(&name));
+        }
+    }
+}
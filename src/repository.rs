@@ -1,7 +1,8 @@
 use crate::*;
 
+use std::collections::*;
 use std::fmt::{self, Debug, Formatter};
-use std::ffi::OsStr;
+use std::ffi::{OsStr, OsString};
 use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
@@ -13,51 +14,157 @@ use std::sync::Arc;
 #[derive(Clone)]
 pub struct Repository {
     dot_git:    Arc<PathBuf>,
+    common_dir: Arc<PathBuf>,
+    work_tree:  Option<Arc<PathBuf>>,
+    options:    Arc<GitOptions>,
+    batch:      Arc<CatFileBatch>,
+    packed:     Arc<PackedRefsCache>,
 }
 
 impl Repository {
+    /// Open a bare repository, or a `.git` directory directly.
+    ///
+    /// Fails with [InvalidData](io::ErrorKind::InvalidData) if `dir` contains a `.git`, [NotFound](io::ErrorKind::NotFound) ([GitError::NotARepository])
+    /// if `dir` isn't a git repository, or e.g. [PermissionDenied](io::ErrorKind::PermissionDenied) if it can't be read.
+    ///
     /// # Examples
     ///
     /// ```rust
     /// let repository = clgit::Repository::from_bare_repository(".git").unwrap();
+    /// assert!(repository.is_bare());
     /// ```
     pub fn from_bare_repository(dir: impl Into<PathBuf>) -> io::Result<Self> {
-        // TODO: canonicalize?
         let dir = dir.into();
-        if dir.join(".git").exists() { return Err(io::Error::new(io::ErrorKind::InvalidData, "not a bare repository")); }
-        Ok(Self { dot_git: Arc::new(dir) })
+        if exists(&dir.join(".git"))? { return Err(io::Error::new(io::ErrorKind::InvalidData, "not a bare repository")); }
+        Self::from_dot_git(dir, None)
     }
 
+    /// Open a repository with a work tree, where `dir` is the root of the checkout (containing `.git`.)
+    ///
+    /// Fails with [InvalidData](io::ErrorKind::InvalidData) if `dir` has no `.git`, [NotFound](io::ErrorKind::NotFound) ([GitError::NotARepository])
+    /// if `.git` isn't a valid git directory, or e.g. [PermissionDenied](io::ErrorKind::PermissionDenied) if it can't be read.
+    ///
     /// # Examples
     ///
     /// ```rust
     /// let repository = clgit::Repository::from_regular_repository(".").unwrap();
+    /// assert!(!repository.is_bare());
     /// ```
     pub fn from_regular_repository(dir: impl AsRef<Path>) -> io::Result<Self> {
-        // TODO: canonicalize?
         let dir = dir.as_ref();
         let dot_git = dir.join(".git");
-        if !dot_git.exists() { return Err(io::Error::new(io::ErrorKind::InvalidData, "not a regular repository")); }
-        Ok(Self { dot_git: Arc::new(dot_git) })
+        if !exists(&dot_git)? { return Err(io::Error::new(io::ErrorKind::InvalidData, "not a regular repository")); }
+        Self::from_dot_git(resolve_dot_git(dot_git)?, Some(dir.to_path_buf()))
     }
 
 
+    /// Open `dir` as a regular repository if it contains `.git`, or as a bare repository otherwise.
+    ///
+    /// Fails with [NotFound](io::ErrorKind::NotFound) ([GitError::NotARepository]) if `dir` isn't a git repository,
+    /// or e.g. [PermissionDenied](io::ErrorKind::PermissionDenied) if it can't be read - so it's safe to use on user input.
+    ///
     /// # Examples
     ///
     /// ```rust
-    /// let repository = clgit::Repository::from_path(".").unwrap();
-    /// let repository = clgit::Repository::from_path(".git").unwrap();
+    /// # use clgit::*;
+    /// let repository = Repository::from_path(".").unwrap();
+    /// let repository = Repository::from_path(".git").unwrap();
+    ///
+    /// let error = Repository::from_path("src").unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    /// assert!(match GitError::find(&error) { Some(GitError::NotARepository(_)) => true, _ => false });
     /// ```
     pub fn from_path(dir: impl AsRef<Path>) -> io::Result<Self> {
         let dir = dir.as_ref();
         let dot_git = dir.join(".git");
-        if dot_git.exists() {
-            Ok(Self { dot_git: Arc::new(dot_git) })
+        if exists(&dot_git)? {
+            Self::from_dot_git(resolve_dot_git(dot_git)?, Some(dir.to_path_buf()))
         } else {
             Self::from_bare_repository(dir)
         }
     }
 
+    /// Find the repository containing `dir` by searching it and it's parents, like `git` itself does.
+    ///
+    /// Honors `GIT_DIR`, `GIT_WORK_TREE`, and `GIT_CEILING_DIRECTORIES` unless [ignore_env](DiscoverOptions::ignore_env) is set.
+    /// As with `git`, `core.bare` and `core.worktree` are honored, and otherwise setting `GIT_DIR` without `GIT_WORK_TREE` makes the current directory the top of the work tree.
+    /// Paths are made absolute and symlinks resolved.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository = Repository::discover("src", &DiscoverOptions::default()).unwrap();
+    /// let work_tree = repository.work_tree().unwrap();
+    /// assert!(work_tree.join("Cargo.toml").exists());
+    /// assert_eq!(repository.git_dir(), work_tree.join(".git"));
+    /// ```
+    pub fn discover(dir: impl AsRef<Path>, options: &DiscoverOptions) -> io::Result<Self> {
+        let discovered = discover(dir.as_ref(), options)?; // already validated
+        Self::from_valid_dot_git(discovered.dot_git, discovered.work_tree)
+    }
+
+    /// `true` if this repository has no work tree
+    pub fn is_bare(&self) -> bool { self.work_tree.is_none() }
+
+    /// The `.git` directory (or the root of a bare repository.)  For linked worktrees, this is the worktree's own directory (e.g. ".git/worktrees/foo"), holding it's `HEAD` and index.
+    pub fn git_dir(&self) -> &Path { &self.dot_git }
+
+    /// The directory holding refs, objects, config, etc. shared between all worktrees.  Same as [git_dir](Self::git_dir) except for linked worktrees.
+    pub fn common_dir(&self) -> &Path { &self.common_dir }
+
+    /// The main worktree, followed by every linked worktree (`git worktree list`)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository  = Repository::from_path(".").unwrap();
+    /// let worktrees   = repository.worktrees().unwrap();
+    /// assert!(worktrees[0].name.is_none());
+    /// for worktree in worktrees.iter() {
+    ///     println!("{:?} {:?} {:?}", worktree.name, worktree.path, worktree.head);
+    /// }
+    /// ```
+    pub fn worktrees(&self) -> io::Result<Vec<Worktree>> {
+        list_worktrees(self)
+    }
+
+    /// The root of the checkout, or `None` for bare repositories
+    pub fn work_tree(&self) -> Option<&Path> { self.work_tree.as_ref().map(|wt| wt.as_path()) }
+
+    /// Run every future `git` command for this repository with `options` (executable, environment, `-c` overrides.)
+    /// Fails if `git` can't be run, or is older than [min_version](GitOptions::min_version).
+    ///
+    /// See [GitOptions] for examples.
+    pub fn with_git_options(self, options: GitOptions) -> io::Result<Self> {
+        let repository = Self { options: Arc::new(options), batch: Default::default(), ..self }; // don't share cat-file sessions spawned with the old options
+        if let Some(required) = repository.options.min_version {
+            let found = repository.git_version()?;
+            if found < required { return Err(GitError::UnsupportedVersion { found, required }.into()); }
+        }
+        Ok(repository)
+    }
+
+    /// The [GitOptions] used to run `git` for this repository
+    pub fn git_options(&self) -> &GitOptions { &self.options }
+
+    /// Run/parse `git version`, returning (major, minor, patch)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository = Repository::from_path(".").unwrap();
+    /// let (major, _minor, _patch) = repository.git_version().unwrap();
+    /// assert!(major >= 2);
+    /// ```
+    pub fn git_version(&self) -> io::Result<(u32, u32, u32)> {
+        let stdout = GitOutput::run(self.git().arg("version"))?.check()?;
+        let stdout = String::from_utf8_lossy(&stdout);
+        parse_git_version(&stdout).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("git version returned unexpected output {:?}", stdout)))
+    }
+
     /// # Examples
     ///
     /// ```rust
@@ -73,9 +180,12 @@ impl Repository {
     /// # examples().unwrap()
     /// ```
     pub fn local_branches(&self) -> io::Result<impl Iterator<Item = io::Result<Branch>>> {
-        let mut branches = Default::default();
-        gather_branches(OsStr::new(""), &self.dot_git.join("refs/heads"), &mut branches)?;
-        Ok(branches.into_iter().map(|(name, commit)| Ok(Branch { name, commit })))
+        self.branches("refs/heads/", false)
+    }
+
+    /// Like [local_branches](Self::local_branches), but also includes symbolic refs as aliases of the branches they resolve to
+    pub fn local_branches_with_aliases(&self) -> io::Result<impl Iterator<Item = io::Result<Branch>>> {
+        self.branches("refs/heads/", true)
     }
 
     /// # Examples
@@ -93,73 +203,657 @@ impl Repository {
     /// # examples().unwrap()
     /// ```
     pub fn remote_branches(&self) -> io::Result<impl Iterator<Item = io::Result<Branch>>> {
-        let mut branches = Default::default();
-        gather_branches(OsStr::new(""), &self.dot_git.join("refs/remotes"), &mut branches)?;
-        Ok(branches.into_iter().map(|(name, commit)| Ok(Branch { name, commit })))
+        self.branches("refs/remotes/", false)
+    }
+
+    /// Like [remote_branches](Self::remote_branches), but also includes symbolic refs (e.g. "origin/HEAD") as aliases of the branches they resolve to
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn examples() -> std::io::Result<()> {
+    /// # let repository = clgit::Repository::from_regular_repository(".")?;
+    /// for branch in repository.remote_branches_with_aliases()? {
+    ///     let branch : clgit::Branch = branch?;
+    ///     match branch.alias_of() {
+    ///         Some(alias_of)  => println!("{} => {}", branch.name().to_string_lossy(), alias_of.to_string_lossy()),
+    ///         None            => println!("{}", branch.name().to_string_lossy()),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # examples().unwrap()
+    /// ```
+    pub fn remote_branches_with_aliases(&self) -> io::Result<impl Iterator<Item = io::Result<Branch>>> {
+        self.branches("refs/remotes/", true)
+    }
+
+    /// Enumerate loose and packed refs matching a `git for-each-ref` style `pattern`, sorted by name.
+    ///
+    /// `pattern` matches literally up to a slash (e.g. "refs/notes", "refs/stash", "refs/namespaces/foo/"),
+    /// or as a glob (e.g. "refs/heads/release/*", "refs/pull/*/head", "refs/**/HEAD").  An empty pattern matches all refs.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn examples() -> std::io::Result<()> {
+    /// # let repository = clgit::Repository::from_regular_repository(".")?;
+    /// for r in repository.refs("refs/heads/*")? {
+    ///     let r : clgit::Reference = r?;
+    ///     assert!(r.name().to_string_lossy().starts_with("refs/heads/"));
+    ///     let _ = r.hash();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # examples().unwrap()
+    /// ```
+    pub fn refs(&self, pattern: &str) -> io::Result<impl Iterator<Item = io::Result<Reference>>> {
+        let packed = self.packed.get(&self.common_dir)?;
+        let mut symbolic = BTreeMap::new();
+        let direct = self.gather_refs::<()>("refs/", &packed, &mut symbolic)?;
+
+        let mut refs = BTreeMap::new();
+        for (name, target) in direct.into_iter().map(|(n, h)| (n, RefTarget::Direct(h))).chain(symbolic.into_iter().map(|(n, s)| (n, RefTarget::Symbolic(s)))) {
+            let mut full = OsString::from("refs/");
+            full.push(&name);
+            if !ref_pattern_matches(pattern, &full.to_string_lossy()) { continue; }
+            let peeled = match (packed.refs.get(&full), &target) {
+                (Some(p), RefTarget::Direct(hash)) if p.hash == *hash => p.peeled.clone(),
+                _ => None,
+            };
+            refs.insert(full.clone(), Reference { name: full, target, peeled });
+        }
+        Ok(refs.into_values().map(Ok))
+    }
+
+    /// Read/parse `HEAD`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository = Repository::from_path(".").unwrap();
+    /// match repository.head().unwrap() {
+    ///     Head::Branch(name)      => println!("on branch {}", name.to_string_lossy()),
+    ///     Head::Detached(commit)  => println!("detached at {}", commit),
+    /// }
+    /// ```
+    pub fn head(&self) -> io::Result<Head> {
+        match self.read_ref("HEAD")? {
+            Some(RefTarget::Symbolic(name)) => Ok(Head::Branch(name)),
+            Some(RefTarget::Direct(hash))   => Ok(Head::Detached(hash.cast())),
+            None                            => Err(io::Error::new(io::ErrorKind::NotFound, "HEAD not found")),
+        }
+    }
+
+    /// Read a single loose or packed ref by it's full name (e.g. "HEAD", "refs/heads/master", "refs/remotes/origin/HEAD") without following symbolic refs.
+    /// Returns `Ok(None)` if the ref doesn't exist.
+    ///
+    /// Fails with [InvalidInput](io::ErrorKind::InvalidInput) if `name` isn't a valid ref name (see `git check-ref-format`), such as "../config" or "refs//heads".
+    pub fn read_ref(&self, name: &str) -> io::Result<Option<RefTarget>> {
+        check_ref_format(name)?;
+        let dir = if is_per_worktree_ref(name) { &self.dot_git } else { &self.common_dir };
+        match std::fs::read_to_string(dir.join(name)) {
+            Ok(blob) => {
+                if let Some(target) = blob.strip_prefix("ref: ") {
+                    Ok(Some(RefTarget::Symbolic(OsString::from(target.trim()))))
+                } else {
+                    Ok(Some(RefTarget::Direct(unknown::Hash::from_str(blob.trim())?)))
+                }
+            },
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                Ok(self.packed.get(&self.common_dir)?.refs.get(OsStr::new(name)).map(|r| RefTarget::Direct(r.hash.clone())))
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Resolve a ref by it's full name (e.g. "HEAD", "refs/remotes/origin/HEAD") to a [Hash](unknown::Hash), following any chain of symbolic refs.
+    /// Returns `Ok(None)` if the ref, or a ref it points to, doesn't exist (e.g. `HEAD` of a freshly `git init`ed repository.)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository = Repository::from_path(".").unwrap();
+    /// if let Some(head) = repository.resolve_ref("HEAD").unwrap() {
+    ///     assert_eq!(repository.cat_file_type(&head).unwrap(), FileType::Commit);
+    /// }
+    /// ```
+    pub fn resolve_ref(&self, name: &str) -> io::Result<Option<unknown::Hash>> {
+        let mut visited = Vec::<OsString>::new();
+        let mut name = OsString::from(name);
+        loop {
+            let target = match name.to_str() {
+                Some(name)  => self.read_ref(name)?,
+                None        => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("non-utf8 ref name {:?}", name))),
+            };
+            match target {
+                None                            => return Ok(None),
+                Some(RefTarget::Direct(hash))   => return Ok(Some(hash)),
+                Some(RefTarget::Symbolic(next)) => {
+                    if visited.contains(&next) || visited.len() >= 100 {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("symbolic ref loop resolving {:?}", visited[0])));
+                    }
+                    visited.push(name);
+                    name = next;
+                },
+            }
+        }
+    }
+
+    /// Lightweight and annotated tags, with annotated tags peeled to their [Commit]s
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn examples() -> std::io::Result<()> {
+    /// # let repository = clgit::Repository::from_regular_repository(".")?;
+    /// for tag in repository.tags()? {
+    ///     let tag : clgit::tag::Ref = tag?;
+    ///     println!("{}", tag.name().to_string_lossy());
+    ///     let _ = tag.commit();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # examples().unwrap()
+    /// ```
+    pub fn tags(&self) -> io::Result<impl Iterator<Item = io::Result<tag::Ref>>> {
+        let repository = self.clone();
+        self.tag_refs(move |hash| Tag::read(&repository, hash).map(|tag| (tag.target_type, tag.target)))
+    }
+
+    /// [tags](Self::tags), reading annotated [Tag]s with `read_tag` (e.g. through a [RepositoryCache])
+    pub(crate) fn tag_refs<'a>(&self, mut read_tag: impl FnMut(&tag::Hash) -> io::Result<(FileType, unknown::Hash)> + 'a) -> io::Result<impl Iterator<Item = io::Result<tag::Ref>> + 'a> {
+        let packed = self.packed.get(&self.common_dir)?;
+        let tags = self.gather_refs("refs/tags/", &packed, &mut BTreeMap::new())?;
+        let repository = self.clone();
+        Ok(tags.into_iter().map(move |(name, target)| {
+            // Reuse packed-refs peeling information, unless a loose ref has overridden the packed one
+            let mut full = OsString::from("refs/tags/");
+            full.push(&name);
+            let known_peel = match packed.refs.get(&full) {
+                Some(p) if p.hash == target && p.peeled.is_some()               => Some(p.peeled.clone()),
+                Some(p) if p.hash == target && packed.is_peeled("refs/tags/")   => Some(None),
+                _                                                               => None,
+            };
+            repository.tag_ref(name, target, known_peel, &mut read_tag)
+        }))
+    }
+
+    /// Run/parse `git rev-parse --verify [rev]`, resolving any revision expression (e.g. "main~3", "v1.2^{tree}", "HEAD@{yesterday}", ":/fix typo") to a [Hash](unknown::Hash).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository = Repository::from_path(".").unwrap();
+    /// let head = repository.rev_parse("HEAD").unwrap();
+    /// let tree = repository.rev_parse("HEAD^{tree}").unwrap();
+    /// assert_eq!(repository.cat_file_type(&head).unwrap(), FileType::Commit);
+    /// assert_eq!(repository.cat_file_type(&tree).unwrap(), FileType::Tree);
+    ///
+    /// match repository.rev_parse("no-such-branch-or-tag") {
+    ///     Err(RevParseError::UnknownRevision(_)) => {},
+    ///     other => panic!("expected UnknownRevision, got {:?}", other),
+    /// }
+    /// ```
+    pub fn rev_parse(&self, rev: &str) -> Result<unknown::Hash, RevParseError> {
+        if rev.starts_with('-') || rev.is_empty() { return Err(RevParseError::UnknownRevision(rev.into())); } // don't let git treat this as a flag
+
+        let git = GitOutput::run(self.git().env("LC_ALL", "C").args(&["rev-parse", "--verify", rev]))?;
+        if !git.status.success() {
+            let stderr = String::from_utf8_lossy(&git.stderr);
+            if stderr.contains(" is ambiguous")                 { return Err(RevParseError::Ambiguous(rev.into())); }
+            if stderr.contains("Needed a single revision")      { return Err(RevParseError::UnknownRevision(rev.into())); }
+        }
+        let stdout = String::from_utf8(git.check()?).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "git rev-parse ... returned non-utf8 hash"))?;
+        Ok(unknown::Hash::from_str(stdout.trim()).map_err(GitError::from)?)
+    }
+
+    /// [rev_parse](Self::rev_parse), then verify the object is of type `expected`.
+    ///
+    /// Annotated [Tag]s are *not* peeled: use e.g. "v1.2^{commit}" to peel as `git` would.
+    pub fn rev_parse_checked(&self, rev: &str, expected: FileType) -> Result<unknown::Hash, RevParseError> {
+        let hash = self.rev_parse(rev)?;
+        let actual = self.cat_file_type(&hash)?;
+        if actual != expected { return Err(RevParseError::WrongType { revision: rev.into(), expected, actual }); }
+        Ok(hash)
+    }
+
+    /// [rev_parse_checked](Self::rev_parse_checked) for a [Commit]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository = Repository::from_path(".").unwrap();
+    /// let head : commit::Hash = repository.rev_parse_commit("HEAD").unwrap();
+    /// match repository.rev_parse_commit("HEAD^{tree}") {
+    ///     Err(RevParseError::WrongType { expected: FileType::Commit, actual: FileType::Tree, .. }) => {},
+    ///     other => panic!("expected WrongType, got {:?}", other),
+    /// }
+    /// ```
+    pub fn rev_parse_commit (&self, rev: &str) -> Result<commit::Hash,  RevParseError> { Ok(self.rev_parse_checked(rev, FileType::Commit)?.cast()) }
+
+    /// [rev_parse_checked](Self::rev_parse_checked) for a [Tree]
+    pub fn rev_parse_tree   (&self, rev: &str) -> Result<tree::Hash,    RevParseError> { Ok(self.rev_parse_checked(rev, FileType::Tree  )?.cast()) }
+
+    /// [rev_parse_checked](Self::rev_parse_checked) for a Blob
+    pub fn rev_parse_blob   (&self, rev: &str) -> Result<blob::Hash,    RevParseError> { Ok(self.rev_parse_checked(rev, FileType::Blob  )?.cast()) }
+
+    /// [rev_parse_checked](Self::rev_parse_checked) for an annotated [Tag]
+    pub fn rev_parse_tag    (&self, rev: &str) -> Result<tag::Hash,     RevParseError> { Ok(self.rev_parse_checked(rev, FileType::Tag   )?.cast()) }
+
+    /// Run/parse `git blame --incremental [commit] -- [path]`, streaming which [Commit] last changed each line of `path` as of `commit`.
+    ///
+    /// Hunks are yielded in the order git finds them, *not* line order - sort by [final_line](BlameHunk::final_line) if needed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository  = Repository::from_path(".").unwrap();
+    /// let head        = repository.rev_parse_commit("HEAD").unwrap();
+    /// let options     = BlameOptions { lines: vec![(1, 5)], ..Default::default() };
+    /// let mut hunks   = repository.blame(&head, "Cargo.toml", &options).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    /// hunks.sort_by_key(|h| h.final_line);
+    /// assert_eq!(hunks.iter().map(|h| h.lines).sum::<usize>(), 5);
+    /// assert_eq!(hunks[0].final_line, 1);
+    /// ```
+    pub fn blame(&self, commit: &commit::Hash, path: impl AsRef<OsStr>, options: &BlameOptions) -> io::Result<impl Iterator<Item = io::Result<BlameHunk>>> {
+        let mut git = self.git();
+        git.args(&["blame", "--incremental"]);
+        for (start, end) in options.lines.iter() { git.arg("-L").arg(format!("{},{}", start, end)); }
+        if let Some(file) = options.ignore_revs_file.as_ref() { git.arg("--ignore-revs-file").arg(file); }
+        for rev in options.ignore_revs.iter() { git.arg("--ignore-rev").arg(rev.to_string()); }
+        git.arg(commit.to_string()).arg("--").arg(path.as_ref());
+        let git = GitChild::spawn(git.stdin(Stdio::null()).stdout(Stdio::piped()))?;
+        Ok(BlameHunks::new(git))
+    }
+
+    /// Run/parse `git status --porcelain=v2 --branch -z`, listing what's staged, modified, unmerged, untracked, etc. in the work tree.
+    /// Fails for bare repositories.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository  = Repository::from_regular_repository(".").unwrap();
+    /// let status      = repository.status(&StatusOptions { untracked: true, ..Default::default() }).unwrap();
+    /// if let Some(branch) = status.branch.head.as_ref() { println!("on branch {}", branch.to_string_lossy()); }
+    /// for entry in status.entries.iter() {
+    ///     match entry {
+    ///         StatusEntry::Changed(change)    => println!("{:?}{:?} {}", change.staged, change.unstaged, change.path.as_str_lossy()),
+    ///         StatusEntry::Untracked(path)    => println!("?? {}", path.as_str_lossy()),
+    ///         other                           => println!("{:?}", other),
+    ///     }
+    /// }
+    /// ```
+    pub fn status(&self, options: &StatusOptions) -> io::Result<Status> {
+        let mut git = self.git_work_tree()?;
+        git.args(&["status", "--porcelain=v2", "--branch", "-z"]);
+        git.arg(if options.untracked { "--untracked-files=all" } else { "--untracked-files=no" });
+        if options.ignored { git.arg("--ignored"); }
+        parse_status(&GitOutput::run(&mut git)?.check()?)
+    }
+
+    /// Read/parse `.git/index` (the staging area).  A missing index is treated as empty.
+    ///
+    /// Falls back on `git ls-files --stage -z` (without stat data or flags) for split indexes, or other [ErrorKind::Unsupported](io::ErrorKind::Unsupported) formats.
+    /// Other parse errors (e.g. a corrupt index) are returned as-is.  Fails with [ErrorKind::InvalidInput](io::ErrorKind::InvalidInput) for bare repositories.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository = Repository::from_path(".").unwrap();
+    /// let index = repository.index().unwrap();
+    /// for (path, stages) in index.conflicts() {
+    ///     println!("{} is conflicted ({} stages)", path.as_str_lossy(), stages.len());
+    /// }
+    /// assert!(index.entry("src/_clgit.rs").is_some());
+    /// ```
+    pub fn index(&self) -> io::Result<Index> {
+        if self.is_bare() { return Err(io::Error::new(io::ErrorKind::InvalidInput, "bare repositories have no index")); }
+        let data = match std::fs::read(self.dot_git.join("index")) {
+            Ok(data) => data,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Index::empty()),
+            Err(e) => return Err(e),
+        };
+        let parsed = if self.object_format()? == "sha256" { Index::parse_sha256(&data) } else { Index::parse(&data) };
+        match parsed {
+            Err(ref e) if e.kind() == io::ErrorKind::Unsupported => self.ls_files_stage(),
+            other => other,
+        }
+    }
+
+    /// The repository's hash algorithm (`extensions.objectFormat`): "sha1" unless configured otherwise
+    fn object_format(&self) -> io::Result<String> {
+        Ok(self.config_get("extensions.objectformat", None)?.map_or_else(|| "sha1".into(), |f| f.to_ascii_lowercase()))
+    }
+
+    /// Run/parse `git config [--type] --get key`, or `None` if `key` isn't set
+    pub(crate) fn config_get(&self, key: &str, ty: Option<&str>) -> io::Result<Option<String>> {
+        let mut git = self.git();
+        git.arg("config");
+        if let Some(ty) = ty { git.arg(format!("--{}", ty)); }
+        let git = GitOutput::run(git.args(&["--get", key]))?;
+        if git.status.code() == Some(1) { return Ok(None); } // unset
+        let stdout = String::from_utf8(git.check()?).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("git config --get {} returned non-utf8", key)))?;
+        Ok(Some(stdout.trim_end_matches(&['\n', '\r'][..]).to_string()))
+    }
+
+    /// Run/parse `git ls-files --stage -z`
+    fn ls_files_stage(&self) -> io::Result<Index> {
+        let stdout = GitOutput::run(self.git_work_tree()?.args(&["ls-files", "--stage", "-z"]))?.check()?;
+        Index::parse_ls_files_stage(&stdout)
+    }
+
+    /// Run/parse `git merge-base --all [a] [b]`.  Mostly useful for verifying [RepositoryCache::merge_bases].
+    pub fn merge_base_all(&self, a: &commit::Hash, b: &commit::Hash) -> io::Result<Vec<commit::Hash>> {
+        let git = GitOutput::run(self.git().args(&["merge-base", "--all"]).arg(a.to_string()).arg(b.to_string()))?;
+        if git.status.code() == Some(1) { return Ok(Vec::new()); } // no common ancestors
+        let stdout = String::from_utf8(git.check()?).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "git merge-base --all ... returned non-utf8 hashes"))?;
+        stdout.lines().map(|line| Ok(commit::Hash::from_str(line.trim()).map_err(GitError::from)?)).collect()
+    }
+
+    /// The full name of the ref `branch` is configured to track (e.g. "master" => "refs/remotes/origin/master"), if any
+    pub fn upstream(&self, branch: &Branch) -> io::Result<Option<OsString>> {
+        let mut full = OsString::from("refs/heads/");
+        full.push(branch.name());
+        let stdout = GitOutput::run(self.git().args(&["for-each-ref", "--format=%(upstream)"]).arg(&full))?.check()?;
+        let stdout = String::from_utf8(stdout).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "git for-each-ref ... returned non-utf8 upstream"))?;
+        let upstream = stdout.lines().next().unwrap_or("").trim();
+        Ok(if upstream.is_empty() { None } else { Some(OsString::from(upstream)) })
+    }
+
+    /// Run/parse `git rev-parse --disambiguate=[prefix]`, listing every object whose [Hash](unknown::Hash) starts with `prefix`
+    pub fn disambiguate(&self, prefix: &HashPrefix) -> io::Result<Vec<unknown::Hash>> {
+        let stdout = GitOutput::run(self.git().arg("rev-parse").arg(format!("--disambiguate={}", prefix)))?.check()?;
+        let stdout = String::from_utf8(stdout).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "git rev-parse --disambiguate=... returned non-utf8 hashes"))?;
+        Ok(stdout.lines().map(|line| unknown::Hash::from_str(line.trim())).collect::<Result<_, _>>().map_err(GitError::from)?)
+    }
+
+    /// Expand an abbreviated [HashPrefix] to a full [Hash](unknown::Hash).
+    /// Unlike [rev_parse](Self::rev_parse), refs are never considered, even if one happens to be named e.g. "beef".
+    /// Use [disambiguate](Self::disambiguate) to list the candidates of an [Ambiguous](RevParseError::Ambiguous) prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository = Repository::from_path(".").unwrap();
+    /// let head  = repository.rev_parse("HEAD").unwrap();
+    /// let short = repository.short_hash(&head).unwrap();
+    /// assert!(short.len() < 40);
+    /// assert_eq!(repository.expand_prefix(&short).unwrap(), head);
+    /// ```
+    pub fn expand_prefix(&self, prefix: &HashPrefix) -> Result<unknown::Hash, RevParseError> {
+        let mut candidates = self.disambiguate(prefix)?;
+        match candidates.len() {
+            0 => Err(RevParseError::UnknownRevision(prefix.to_string())),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(RevParseError::Ambiguous(prefix.to_string())),
+        }
+    }
+
+    /// Run/parse `git rev-parse --short [hash]`: the shortest unambiguous abbreviation of `hash`, honoring `core.abbrev`
+    pub fn short_hash<T>(&self, hash: &generic::Hash<T>) -> io::Result<HashPrefix> {
+        let hash = HashTempStr::new(hash);
+        let stdout = GitOutput::run(self.git().args(&["rev-parse", "--short", hash.as_str()]))?.check()?;
+        let stdout = String::from_utf8(stdout).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "git rev-parse --short ... returned non-utf8 hash"))?;
+        Ok(HashPrefix::from_str(stdout.trim()).map_err(GitError::from)?)
     }
 
     /// Run/parse `git cat-file -s [hash]`
     pub fn cat_file_size(&self, hash: &blob::Hash) -> io::Result<u64> {
-        let hash = HashTempStr::new(hash);
-        let git = self.git().args(&["cat-file", "-s", hash.as_str()]).output()?;
-        match git.status.code() {
-            Some(0) => {},
-            Some(_) => return Err(io::Error::new(io::ErrorKind::Other, "git cat-file -s ... exited non-zero")),
-            None    => return Err(io::Error::new(io::ErrorKind::Other, "git cat-file -s ... died by signal")),
+        match self.batch.info(self, &hash.typeless()) {
+            Ok(Some((_, size))) => return Ok(size),
+            Ok(None)            => return Err(missing_object(hash)),
+            Err(_)              => {}, // fall back on a one-shot process
         }
-        Ok(String::from_utf8(git.stdout)
+
+        let hash = HashTempStr::new(hash);
+        let stdout = GitOutput::run(self.git().args(&["cat-file", "-s", hash.as_str()]))?.check()?;
+        String::from_utf8(stdout)
             .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "git cat-file -s ... returned non-utf8 size"))?
             .trim()
             .parse()
-            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "git cat-file -s ... returned non-u64 file size"))?
-        )
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "git cat-file -s ... returned non-u64 file size"))
     }
 
     /// Run/parse `git cat-file -t [hash]`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository = Repository::from_path(".").unwrap();
+    /// for branch in repository.local_branches().unwrap() {
+    ///     let branch = branch.unwrap();
+    ///     assert_eq!(repository.cat_file_type(&branch.commit().typeless()).unwrap(), FileType::Commit);
+    /// }
+    /// ```
     pub fn cat_file_type(&self, hash: &unknown::Hash) -> io::Result<FileType> {
-        let hash = HashTempStr::new(hash);
-        let git = self.git().args(&["cat-file", "-t", hash.as_str()]).output()?;
-        match git.status.code() {
-            Some(0) => {},
-            Some(_) => return Err(io::Error::new(io::ErrorKind::Other, "git cat-file -t ... exited non-zero")),
-            None    => return Err(io::Error::new(io::ErrorKind::Other, "git cat-file -t ... died by signal")),
+        match self.batch.info(self, hash) {
+            Ok(Some((ty, _)))   => return Ok(ty),
+            Ok(None)            => return Err(missing_object(hash)),
+            Err(_)              => {}, // fall back on a one-shot process
         }
-        Ok(String::from_utf8(git.stdout).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "git cat-file -t ... returned non-utf8 type"))?.trim().into())
+
+        let hash = HashTempStr::new(hash);
+        let stdout = GitOutput::run(self.git().args(&["cat-file", "-t", hash.as_str()]))?.check()?;
+        Ok(String::from_utf8(stdout).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "git cat-file -t ... returned non-utf8 type"))?.trim().into())
+    }
+
+    /// Run/parse `git cat-file --batch-check` for many objects at once, streaming back an [ObjectInfo] per hash.
+    ///
+    /// Missing objects are reported as [io::ErrorKind::NotFound] items without ending iteration.
+    /// `hashes` is consumed lazily on a background thread as `git` is ready for more.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository = Repository::from_path(".").unwrap();
+    /// let commits = repository.local_branches().unwrap().map(|b| b.unwrap().commit().typeless());
+    /// let fields  = ObjectInfoFields { disk_size: true, ..Default::default() };
+    /// for info in repository.cat_file_batch_check(commits, fields).unwrap() {
+    ///     let info = info.unwrap();
+    ///     assert_eq!(info.file_type, FileType::Commit);
+    ///     assert!(info.disk_size.is_some());
+    ///     assert!(info.delta_base.is_none());
+    /// }
+    /// ```
+    pub fn cat_file_batch_check<I: IntoIterator<Item = unknown::Hash>>(&self, hashes: I, fields: ObjectInfoFields) -> io::Result<impl Iterator<Item = io::Result<ObjectInfo>>>
+        where I::IntoIter: Send + 'static
+    {
+        let hashes : Box<dyn Iterator<Item = unknown::Hash> + Send> = Box::new(hashes.into_iter());
+        let git = GitChild::spawn(self.git()
+            .args(&["cat-file".into(), fields.batch_check_arg(), "--buffer".into()])
+            .stdin (Stdio::piped())
+            .stdout(Stdio::piped())
+        )?;
+        Ok(ObjectInfos::new(git, Some(hashes), fields))
+    }
+
+    /// Run/parse `git cat-file --batch-check --batch-all-objects`, streaming back an [ObjectInfo] for every object in the repository.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let repository = Repository::from_path(".").unwrap();
+    /// let mut largest = 0;
+    /// for info in repository.cat_file_batch_all_objects(Default::default()).unwrap() {
+    ///     let info = info.unwrap();
+    ///     if info.file_type == FileType::Blob { largest = largest.max(info.size); }
+    /// }
+    /// assert!(largest > 0);
+    /// ```
+    pub fn cat_file_batch_all_objects(&self, fields: ObjectInfoFields) -> io::Result<impl Iterator<Item = io::Result<ObjectInfo>>> {
+        let git = GitChild::spawn(self.git()
+            .args(&["cat-file".into(), fields.batch_check_arg(), "--batch-all-objects".into()])
+            .stdin (Stdio::null())
+            .stdout(Stdio::piped())
+        )?;
+        Ok(ObjectInfos::new(git, None, fields))
     }
 
     /// Run/parse `git cat-file commit [hash]`
-    pub fn cat_file_commit  (&self, hash: &commit::Hash) -> io::Result<impl Read> { self.cat_file("commit", hash) }
+    pub fn cat_file_commit  (&self, hash: &commit::Hash) -> io::Result<impl Read> { self.cat_file(FileType::Commit, hash) }
+
+    /// Run/parse `git cat-file tag [hash]`
+    pub fn cat_file_tag     (&self, hash: &tag::Hash) -> io::Result<impl Read> { self.cat_file(FileType::Tag, hash) }
 
     /// Run/parse `git cat-file tree [hash]`
-    pub fn cat_file_tree    (&self, hash: &tree::Hash) -> io::Result<impl Read> { self.cat_file("tree", hash) }
+    pub fn cat_file_tree    (&self, hash: &tree::Hash) -> io::Result<impl Read> { self.cat_file(FileType::Tree, hash) }
 
     /// Run/parse `git cat-file blob [hash]`
-    pub fn cat_file_blob    (&self, hash: &blob::Hash) -> io::Result<impl Read> { self.cat_file("blob", hash) }
+    pub fn cat_file_blob    (&self, hash: &blob::Hash) -> io::Result<impl Read> { self.cat_file(FileType::Blob, hash) }
+
+    /// Validate and canonicalize `dot_git` and `work_tree`
+    pub(crate) fn from_dot_git(dot_git: PathBuf, work_tree: Option<PathBuf>) -> io::Result<Self> {
+        validate_git_dir(&dot_git)?;
+        Self::from_valid_dot_git(dot_git, work_tree)
+    }
 
-    fn git(&self) -> Command {
-        let mut c = Command::new("git");
-        c.current_dir(&*self.dot_git);
-        c
+    /// Canonicalize `dot_git` and `work_tree`, which the caller has already validated
+    pub(crate) fn from_valid_dot_git(dot_git: PathBuf, work_tree: Option<PathBuf>) -> io::Result<Self> {
+        let dot_git     = canonicalize(&dot_git)?;
+        let work_tree   = match work_tree { Some(wt) => Some(canonicalize(&wt)?), None => None };
+        Ok(Self {
+            common_dir: Arc::new(common_dir(&dot_git)?),
+            dot_git:    Arc::new(dot_git),
+            work_tree:  work_tree.map(Arc::new),
+            options:    Default::default(),
+            batch:      Default::default(),
+            packed:     Default::default(),
+        })
+    }
+
+    fn branches(&self, prefix: &str, aliases: bool) -> io::Result<impl Iterator<Item = io::Result<Branch>>> {
+        let mut symbolic = BTreeMap::new();
+        let mut branches : BTreeMap<OsString, io::Result<(commit::Hash, Option<OsString>)>> = self.gather_refs(prefix, &*self.packed.get(&self.common_dir)?, &mut symbolic)?
+            .into_iter().map(|(name, commit)| (name, Ok((commit, None)))).collect();
+        if aliases {
+            for (name, alias_of) in symbolic {
+                let branch = match alias_of.to_str().map(|a| self.resolve_ref(a)) {
+                    Some(Ok(Some(commit)))  => Ok((commit.cast(), Some(alias_of))),
+                    Some(Ok(None))          => continue, // dangling symbolic ref
+                    Some(Err(e))            => Err(e),
+                    None                    => Err(io::Error::new(io::ErrorKind::InvalidData, format!("non-utf8 symbolic ref target {:?}", alias_of))),
+                };
+                branches.insert(name, branch);
+            }
+        }
+        Ok(branches.into_iter().map(|(name, branch)| branch.map(|(commit, alias_of)| Branch { name, commit, alias_of })))
+    }
+
+    /// Gather loose and packed refs starting with `prefix` (e.g. "refs/heads/"), with loose refs taking priority.
+    /// Loose symbolic refs are gathered into `symbolic` instead.
+    fn gather_refs<T>(&self, prefix: &str, packed: &PackedRefs, symbolic: &mut BTreeMap<OsString, OsString>) -> io::Result<BTreeMap<OsString, generic::Hash<T>>> {
+        let mut refs : BTreeMap<_, _> = packed.prefixed(prefix).map(|(name, r)| (name, r.hash.cast())).collect();
+        gather_branches(OsStr::new(""), &self.common_dir.join(prefix), &mut refs, symbolic)?;
+
+        if self.common_dir != self.dot_git { // linked worktree: per-worktree refs come from it's own git dir, not the main worktree's
+            if PER_WORKTREE_REF_DIRS.iter().any(|dir| prefix.starts_with(dir)) { // e.g. "refs/bisect/": every ref is per-worktree
+                refs.clear();
+                symbolic.clear();
+                let path = self.dot_git.join(prefix);
+                if path.is_dir() { gather_branches(OsStr::new(""), &path, &mut refs, symbolic)?; }
+                return Ok(refs);
+            }
+            for dir in PER_WORKTREE_REF_DIRS.iter().filter(|dir| dir.starts_with(prefix) && dir.len() > prefix.len()) {
+                let name = &dir[prefix.len() .. dir.len()-1]; // e.g. "bisect" for prefix "refs/"
+                let in_dir = |n: &OsString| matches!(n.to_str(), Some(n) if n.starts_with(name) && n[name.len()..].starts_with('/'));
+                for n in refs    .keys().filter(|n| in_dir(n)).cloned().collect::<Vec<_>>() { refs    .remove(&n); }
+                for n in symbolic.keys().filter(|n| in_dir(n)).cloned().collect::<Vec<_>>() { symbolic.remove(&n); }
+                let path = self.dot_git.join(dir);
+                if path.is_dir() { gather_branches(OsStr::new(name), &path, &mut refs, symbolic)?; }
+            }
+        }
+        Ok(refs)
     }
 
-    fn cat_file<T>(&self, ty: &str, hash: &generic::Hash<T>) -> io::Result<impl Read> {
+    /// `known_peel` is `Some(Some(peeled))` for known annotated tags, `Some(None)` for known lightweight tags, or `None` if unknown.
+    fn tag_ref(&self, name: OsString, target: unknown::Hash, known_peel: Option<Option<unknown::Hash>>, read_tag: impl FnMut(&tag::Hash) -> io::Result<(FileType, unknown::Hash)>) -> io::Result<tag::Ref> {
+        let (annotated, ty, peeled) = match known_peel {
+            Some(Some(peeled))  => (true,  self.cat_file_type(&peeled)?, peeled),
+            Some(None)          => (false, self.cat_file_type(&target)?, target.clone()),
+            None                => {
+                let ty = self.cat_file_type(&target)?;
+                (ty == FileType::Tag, ty, target.clone())
+            },
+        };
+        let commit = match tag::peel(ty, peeled, read_tag)? {
+            (FileType::Commit, commit)  => Some(commit.cast()),
+            _other                      => None,
+        };
+        Ok(tag::Ref { name, target, annotated, commit })
+    }
+
+    /// A `git` [Command] bound to this repository's git directory (whatever `GIT_DIR` the caller inherited), run from within it
+    pub(crate) fn git(&self) -> Command {
+        self.options.command(&self.dot_git, None)
+    }
+
+    /// Like [git](Self::git), but run from the root of the work tree, for commands that need one (e.g. `git status`)
+    pub(crate) fn git_work_tree(&self) -> io::Result<Command> {
+        let work_tree = self.work_tree.as_ref().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "bare repositories have no work tree"))?;
+        Ok(self.options.command(&self.dot_git, Some(work_tree)))
+    }
+
+    /// Read an object through the shared `git cat-file --batch` session, falling back on a one-shot `git cat-file [ty] [hash]`
+    /// if the session is unavailable or the object isn't exactly `ty` (e.g. an annotated tag `git cat-file` would peel for us.)
+    fn cat_file<T>(&self, ty: FileType, hash: &generic::Hash<T>) -> io::Result<CatFileContents> {
+        let buffered = ty != FileType::Blob || match self.batch.info(self, &hash.typeless()) {
+            Ok(Some((_, size))) => size <= MAX_BATCH_BLOB_SIZE,
+            Ok(None)            => return Err(missing_object(hash)),
+            Err(_)              => false,
+        };
+        if buffered {
+            match self.batch.contents(self, &hash.typeless()) {
+                Ok(Some((actual, data))) if actual == ty => return Ok(CatFileContents::Batch(io::Cursor::new(data))),
+                Ok(Some(_))     => {}, // let git decide if it can peel/convert
+                Ok(None)        => return Err(missing_object(hash)),
+                Err(_)          => {}, // fall back on a one-shot process
+            }
+        }
+
         let hash = HashTempStr::new(hash);
-        let mut git = self.git()
-            .args(&["cat-file", ty, hash.as_str()])
+        let mut git = GitChild::spawn(self.git()
+            .args(&["cat-file", ty.as_str(), hash.as_str()])
             .stdin (Stdio::null())
-            .stderr(Stdio::null())
             .stdout(Stdio::piped())
-            .spawn()?;
-        Ok(CatFileReader { stdout: git.stdout.take().unwrap(), child: git })
+        )?;
+        Ok(CatFileContents::Process(CatFileReader { stdout: git.child.stdout.take().unwrap(), child: git }))
     }
 }
 
+/// Larger blobs are streamed from a one-shot `git cat-file` instead of being buffered whole by [CatFileBatch]
+const MAX_BATCH_BLOB_SIZE : u64 = 1 << 20;
+
+/// Like [Path::exists], but passes along errors other than [NotFound](io::ErrorKind::NotFound) (e.g. [PermissionDenied](io::ErrorKind::PermissionDenied))
+fn exists(path: &Path) -> io::Result<bool> {
+    match std::fs::metadata(path) {
+        Ok(_)                                               => Ok(true),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound   => Ok(false),
+        Err(e)                                              => Err(e),
+    }
+}
+
+fn missing_object<T>(hash: &generic::Hash<T>) -> io::Error {
+    GitError::ObjectNotFound(hash.typeless()).into()
+}
+
 impl Debug for Repository {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
         fmt.debug_struct("Repository")
-            .field("dot_git", &self.dot_git)
-            .field("local_branches",    &self.local_branches().map(|b| b.collect::<Vec<_>>()))
-            .field("remote_branches",   &self.remote_branches().map(|r| r.collect::<Vec<_>>()))
+            .field("dot_git",       &self.dot_git)
+            .field("common_dir",    &self.common_dir)
+            .field("work_tree",     &self.work_tree)
             .finish()
     }
 }
@@ -7,13 +7,14 @@ use std::sync::Arc;
 
 
 
-/// A git [Repository] + in-memory caches for [Commit]s, [Tree]s, and possibly Blobs
+/// A git [Repository] + in-memory caches for [Commit]s, [Tree]s, [Tag]s, and possibly Blobs
 pub struct RepositoryCache {
     /// The [Repository] this cache reads from
     pub         repository: Repository,
 
     commits:    SharedHashMap<Commit, Arc<Commit>>,
     trees:      SharedHashMap<Tree,   Arc<Tree>  >,
+    tags:       SharedHashMap<Tag,    Arc<Tag>   >,
 }
 
 impl RepositoryCache {
@@ -23,6 +24,7 @@ impl RepositoryCache {
             repository,
             commits:    Default::default(),
             trees:      Default::default(),
+            tags:       Default::default(),
         }
     }
 
@@ -57,6 +59,277 @@ impl RepositoryCache {
             Ok(tree)
         }
     }
+
+    /// Attempt to read an annotated [Tag] by it's given [Hash](tag::Hash)
+    pub fn tag(&self, hash: &tag::Hash) -> io::Result<Arc<Tag>> {
+        if let Some(tag) = self.tags.get_clone(hash) {
+            return Ok(tag);
+        }
+
+        let tag = Arc::new(Tag::read(&self.repository, hash)?);
+        let mut bucket = self.tags.bucket_for(hash).lock().unwrap();
+        if let Some(tag) = bucket.get(hash) {
+            Ok(tag.clone())
+        } else {
+            bucket.insert(hash.clone(), tag.clone());
+            Ok(tag)
+        }
+    }
+
+    /// Resolve a slash-separated `path` (e.g. "src/foo/bar.rs") within the root [Tree] of `commit`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let head    = cache.repository.rev_parse_commit("HEAD").unwrap();
+    ///
+    /// let entry   = cache.commit_entry_at(&head, "src/_clgit.rs").unwrap();
+    /// assert_eq!(entry.name, "_clgit.rs");
+    /// assert!(entry.blob_hash().is_some());
+    ///
+    /// match cache.commit_entry_at(&head, "src/_clgit.rs/nope") {
+    ///     Err(PathError::NotADirectory(path)) => assert_eq!(path, "src/_clgit.rs"),
+    ///     other => panic!("expected NotADirectory, got {:?}", other),
+    /// }
+    /// match cache.commit_entry_at(&head, "src/nope.rs") {
+    ///     Err(PathError::NotFound(path)) => assert_eq!(path, "src/nope.rs"),
+    ///     other => panic!("expected NotFound, got {:?}", other),
+    /// }
+    /// ```
+    pub fn commit_entry_at(&self, commit: &commit::Hash, path: impl AsRef<[u8]>) -> Result<tree::Entry, PathError> {
+        let commit = self.commit(commit)?;
+        self.tree_entry_at(&commit.tree, path)
+    }
+
+    /// Resolve a slash-separated `path` (e.g. "src/foo/bar.rs", or a [Name] from a [TreeChange]) within `tree`, reading subtrees through this cache
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let head    = cache.repository.rev_parse_commit("HEAD").unwrap();
+    /// let tree    = cache.commit(&head).unwrap().tree.clone();
+    /// let path    = Name::from("src/_clgit.rs");
+    /// assert_eq!(cache.tree_entry_at(&tree, &path).unwrap().name, "_clgit.rs");
+    /// ```
+    pub fn tree_entry_at(&self, tree: &tree::Hash, path: impl AsRef<[u8]>) -> Result<tree::Entry, PathError> {
+        let mut components = path.as_ref().split(|b| *b == b'/').filter(|c| !c.is_empty()).peekable();
+        let mut tree = self.tree(tree)?;
+        let mut walked = Vec::new();
+
+        while let Some(component) = components.next() {
+            if !walked.is_empty() { walked.push(b'/'); }
+            walked.extend_from_slice(component);
+
+            let entry = match tree.entries.get(&Name::from(component)) {
+                Some(entry) => entry,
+                None        => return Err(PathError::NotFound(Name::from(walked))),
+            };
+
+            if components.peek().is_none() { return Ok(entry.clone()); }
+
+            if let Some(subtree) = entry.tree_hash() {
+                tree = self.tree(&subtree)?;
+            } else if entry.mode.is_submodule() {
+                return Err(PathError::CrossedSubmodule(Name::from(walked)));
+            } else {
+                return Err(PathError::NotADirectory(Name::from(walked)));
+            }
+        }
+
+        Err(PathError::NotFound(Name::from(walked))) // empty path
+    }
+
+    /// Recursively walk `tree`, yielding (full path, [Entry](tree::Entry)) pairs in `order`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let tree    = cache.repository.rev_parse_tree("HEAD^{tree}").unwrap();
+    /// let paths   = cache.walk_tree(&tree, WalkOrder::PreOrder).unwrap()
+    ///     .map(|e| e.unwrap().0.as_str_lossy().to_owned())
+    ///     .collect::<Vec<_>>();
+    /// let src     = paths.iter().position(|p| p == "src").unwrap();
+    /// let clgit   = paths.iter().position(|p| p == "src/_clgit.rs").unwrap();
+    /// assert!(src < clgit);
+    /// ```
+    pub fn walk_tree(&self, tree: &tree::Hash, order: WalkOrder) -> io::Result<TreeWalk<'_>> {
+        TreeWalk::new(self, tree, order)
+    }
+
+    /// Recursively visit every (full path, [Entry](tree::Entry)) of `tree` across `threads` scoped threads, in unspecified order.
+    ///
+    /// `visit` returns whether or not to descend into a given subtree (it's return value is ignored for anything else).
+    /// Submodules are never descended into.  The first error encountered stops the walk and is returned.
+    /// `visit` may borrow from the caller: every thread has finished by the time this returns.
+    /// If `visit` panics, the walk stops and the panic is resumed on the calling thread.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// # use std::sync::atomic::{AtomicUsize, Ordering};
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let tree    = cache.repository.rev_parse_tree("HEAD^{tree}").unwrap();
+    /// let files   = AtomicUsize::new(0);
+    /// cache.par_walk_tree(&tree, 4, |_path, entry| {
+    ///     if entry.mode.is_blob() { files.fetch_add(1, Ordering::Relaxed); }
+    ///     true
+    /// }).unwrap();
+    /// assert!(files.load(Ordering::Relaxed) > 0);
+    /// ```
+    pub fn par_walk_tree(&self, tree: &tree::Hash, threads: usize, visit: impl Fn(&Name, &tree::Entry) -> bool + Sync) -> io::Result<()> {
+        par_walk_tree(self, tree, threads, visit)
+    }
+
+    /// Recursively compare two [Tree]s, returning every added, deleted, or otherwise changed file (Blob or submodule), ordered by path.
+    ///
+    /// Subtrees with identical hashes are skipped without being read.  Pass `None` for `old` or `new` to diff against an empty tree
+    /// (e.g. for a root [Commit]).  A subtree replaced by a file (or vice versa) is reported as deletions + additions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let tree    = cache.repository.rev_parse_tree("HEAD^{tree}").unwrap();
+    /// assert!(cache.diff_trees(Some(&tree), Some(&tree), TreeDiffOptions::default()).unwrap().is_empty());
+    ///
+    /// let added   = cache.diff_trees(None, Some(&tree), TreeDiffOptions::default()).unwrap();
+    /// assert!(added.iter().all(|c| match c { TreeChange::Added { .. } => true, _ => false }));
+    /// assert!(added.iter().any(|c| c.path() == "src/_clgit.rs"));
+    /// ```
+    pub fn diff_trees(&self, old: Option<&tree::Hash>, new: Option<&tree::Hash>, options: TreeDiffOptions) -> io::Result<Vec<TreeChange>> {
+        tree_diff::diff_trees(self, old, new, options)
+    }
+
+    /// Walk [Commit] history, like `git rev-list`.  Configure the returned [RevWalk] before iterating.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let head    = cache.repository.rev_parse_commit("HEAD").unwrap();
+    /// let recent  = cache.rev_walk().push(&head).order(RevWalkOrder::Topo).max_count(10)
+    ///     .collect::<Result<Vec<_>, _>>().unwrap();
+    /// assert_eq!(recent[0].hash, head);
+    /// for (i, commit) in recent.iter().enumerate() {
+    ///     assert!(recent[..i].iter().all(|newer| !commit.parents.contains(&newer.hash)));
+    /// }
+    /// ```
+    pub fn rev_walk(&self) -> RevWalk<'_> {
+        RevWalk::new(self)
+    }
+
+    /// The best common ancestors of `a` and `b`, newest first (`git merge-base --all a b`).  Usually just one, empty for unrelated histories.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let head    = cache.repository.rev_parse_commit("HEAD").unwrap();
+    /// let prev    = cache.repository.rev_parse_commit("HEAD~2").unwrap();
+    /// assert_eq!(cache.merge_bases(&head, &prev).unwrap(), vec![prev.clone()]);
+    /// assert_eq!(cache.merge_bases(&head, &prev).unwrap(), cache.repository.merge_base_all(&head, &prev).unwrap());
+    /// ```
+    pub fn merge_bases(&self, a: &commit::Hash, b: &commit::Hash) -> io::Result<Vec<commit::Hash>> {
+        merge_base::merge_bases(self, a, std::slice::from_ref(b))
+    }
+
+    /// The newest of [merge_bases](Self::merge_bases), if any (`git merge-base a b`)
+    pub fn merge_base(&self, a: &commit::Hash, b: &commit::Hash) -> io::Result<Option<commit::Hash>> {
+        Ok(self.merge_bases(a, b)?.into_iter().next())
+    }
+
+    /// The best common ancestors of every commit in `commits`, for an n-way merge (`git merge-base --octopus`)
+    pub fn octopus_merge_bases(&self, commits: &[commit::Hash]) -> io::Result<Vec<commit::Hash>> {
+        merge_base::octopus_merge_bases(self, commits)
+    }
+
+    /// The subset of `commits` not reachable from any of the others, newest first (`git merge-base --independent`)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let head    = cache.repository.rev_parse_commit("HEAD").unwrap();
+    /// let prev    = cache.repository.rev_parse_commit("HEAD~1").unwrap();
+    /// assert_eq!(cache.independent(&[prev, head.clone()]).unwrap(), vec![head]);
+    /// ```
+    pub fn independent(&self, commits: &[commit::Hash]) -> io::Result<Vec<commit::Hash>> {
+        merge_base::independent(self, commits)
+    }
+
+    /// `true` if `ancestor` is reachable from `descendant` (or they're the same commit) (`git merge-base --is-ancestor`)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let head    = cache.repository.rev_parse_commit("HEAD").unwrap();
+    /// let prev    = cache.repository.rev_parse_commit("HEAD~1").unwrap();
+    /// assert!( cache.is_ancestor(&prev, &head).unwrap());
+    /// assert!(!cache.is_ancestor(&head, &prev).unwrap());
+    /// ```
+    pub fn is_ancestor(&self, ancestor: &commit::Hash, descendant: &commit::Hash) -> io::Result<bool> {
+        merge_base::is_ancestor(self, ancestor, descendant)
+    }
+
+    /// How many commits `a` has that `b` doesn't, and vice versa (`git rev-list --count --left-right a...b`)
+    pub fn ahead_behind(&self, a: &commit::Hash, b: &commit::Hash) -> io::Result<(usize, usize)> {
+        merge_base::ahead_behind(self, a, b)
+    }
+
+    /// [ahead_behind](Self::ahead_behind) a local `branch` and it's [upstream](Repository::upstream), or `None` if it has no upstream
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// for branch in cache.repository.local_branches().unwrap() {
+    ///     let branch = branch.unwrap();
+    ///     if let Some((ahead, behind)) = cache.ahead_behind_upstream(&branch).unwrap() {
+    ///         println!("{}: {} ahead, {} behind", branch.name().to_string_lossy(), ahead, behind);
+    ///     }
+    /// }
+    /// ```
+    pub fn ahead_behind_upstream(&self, branch: &Branch) -> io::Result<Option<(usize, usize)>> {
+        let upstream = match self.repository.upstream(branch)? { Some(u) => u, None => return Ok(None) };
+        let upstream = upstream.to_str().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("non-utf8 upstream {:?}", upstream)))?;
+        let upstream = match self.repository.resolve_ref(upstream)? { Some(u) => u, None => return Ok(None) }; // upstream gone
+        Ok(Some(self.ahead_behind(branch.commit(), &upstream.cast())?))
+    }
+
+    /// Follow a chain of annotated [Tag]s to the first non-[Tag] object (typically a [Commit])
+    pub fn peel_tag(&self, hash: &tag::Hash) -> io::Result<(FileType, unknown::Hash)> {
+        tag::peel(FileType::Tag, hash.typeless(), |hash| self.tag(hash).map(|tag| (tag.target_type.clone(), tag.target.clone())))
+    }
+
+    /// Like [Repository::tags], but reads annotated [Tag]s through this cache
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// for tag in cache.tags().unwrap() {
+    ///     let tag = tag.unwrap();
+    ///     if let Some(hash) = tag.tag() { assert!(cache.tag(&hash).is_ok()); }
+    /// }
+    /// ```
+    pub fn tags(&self) -> io::Result<impl Iterator<Item = io::Result<tag::Ref>> + '_> {
+        self.repository.tag_refs(move |hash| self.tag(hash).map(|tag| (tag.target_type.clone(), tag.target.clone())))
+    }
 }
 
 impl From<Repository> for RepositoryCache {
@@ -73,6 +346,7 @@ pub trait TryIntoSharedRepositoryCache {
     /// Try to convert into an [Arc]\<[RepositoryCache]\> on pain of [std::io::Error].
     ///
     /// May return an [std::io::Error] on an invalid path, invalid git repository, or any number of other error cases.
+    /// Paths are opened with [Repository::from_path], which validates the repository, so converting user input is safe.
     fn try_into_src(self) -> io::Result<Arc<RepositoryCache>>;
 }
 
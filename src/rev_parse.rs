@@ -0,0 +1,66 @@
+use crate::*;
+
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+
+
+/// Describes how [Repository::rev_parse] (or a checked variant) failed.
+/// Convertable to [std::io::Error], [Box]&lt;dyn [std::error::Error]&gt;.
+#[derive(Debug)]
+pub enum RevParseError {
+    /// The revision (typically an abbreviated hash) matches multiple objects
+    Ambiguous(String),
+
+    /// The revision doesn't name any object in the repository
+    UnknownRevision(String),
+
+    /// The revision was resolved, but to an object of the wrong type
+    WrongType {
+        /// The revision that was resolved
+        revision:   String,
+        /// What the object was expected to be
+        expected:   FileType,
+        /// What the object actually was
+        actual:     FileType,
+    },
+
+    /// Running or parsing `git rev-parse` failed for some other reason
+    Io(io::Error),
+}
+
+impl std::error::Error for RevParseError {}
+
+impl Display for RevParseError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            RevParseError::Ambiguous(rev)       => write!(fmt, "Ambiguous revision {:?}", rev),
+            RevParseError::UnknownRevision(rev) => write!(fmt, "Unknown revision {:?}", rev),
+            RevParseError::WrongType { revision, expected, actual } => write!(fmt, "Revision {:?} is a {}, expected a {}", revision, actual, expected),
+            RevParseError::Io(e)                => write!(fmt, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for RevParseError {
+    fn from(e: io::Error) -> Self { RevParseError::Io(e) }
+}
+
+impl From<HashParseError> for RevParseError {
+    fn from(e: HashParseError) -> Self { RevParseError::Io(e.into()) }
+}
+
+impl From<GitError> for RevParseError {
+    fn from(e: GitError) -> Self { RevParseError::Io(e.into()) }
+}
+
+impl From<RevParseError> for io::Error {
+    fn from(rpe: RevParseError) -> Self {
+        match rpe {
+            RevParseError::Io(e)                    => e,
+            RevParseError::Ambiguous(_)             => io::Error::new(io::ErrorKind::InvalidInput, rpe),
+            RevParseError::UnknownRevision(_)       => io::Error::new(io::ErrorKind::NotFound, rpe),
+            RevParseError::WrongType { .. }         => io::Error::new(io::ErrorKind::InvalidData, rpe),
+        }
+    }
+}
@@ -0,0 +1,423 @@
+use crate::*;
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::*;
+use std::io;
+use std::sync::Arc;
+
+
+
+/// The order in which a [RevWalk] yields [Commit]s
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevWalkOrder {
+    /// Newest [commit date](Commit::committer) first, like plain `git rev-list`.  Yields [Commit]s as they're read.
+    Default,
+
+    /// Never yield a parent before all of it's children, otherwise newest commit date first (`git rev-list --date-order`)
+    Date,
+
+    /// Never yield a parent before all of it's children, keeping lines of history together (`git rev-list --topo-order`)
+    Topo,
+}
+
+/// An iterator over the history of one or more [Commit]s, created by [RepositoryCache::rev_walk] (the equivalent of `git rev-list`).
+///
+/// Configure with the builder methods ([push](Self::push), [hide](Self::hide), [range](Self::range), [order](Self::order), ...)
+/// before iterating.  [Commit]s are read through the [RepositoryCache].
+///
+/// Like `git`, excluded history is found by walking in commit date order:
+/// badly skewed commit timestamps can cause commits that should've been excluded to be yielded.
+pub struct RevWalk<'c> {
+    cache:          &'c RepositoryCache,
+    order:          RevWalkOrder,
+    reverse:        bool,
+    first_parent:   bool,
+    max_count:      Option<usize>,
+    yielded:        usize,
+    queue:          Queue,
+    sorted:         Option<std::vec::IntoIter<Arc<Commit>>>,
+    done:           bool,
+}
+
+impl<'c> RevWalk<'c> {
+    pub(crate) fn new(cache: &'c RepositoryCache) -> Self {
+        Self {
+            cache,
+            order:          RevWalkOrder::Default,
+            reverse:        false,
+            first_parent:   false,
+            max_count:      None,
+            yielded:        0,
+            queue:          Queue::default(),
+            sorted:         None,
+            done:           false,
+        }
+    }
+
+    /// Include `commit` and it's ancestors (`git rev-list commit`)
+    pub fn push(mut self, commit: &commit::Hash) -> Self {
+        self.queue.start(commit, 0);
+        self
+    }
+
+    /// Exclude `commit` and it's ancestors (`git rev-list ^commit`)
+    pub fn hide(mut self, commit: &commit::Hash) -> Self {
+        self.queue.start(commit, UNINTERESTING);
+        self
+    }
+
+    /// Include commits reachable from either `a` or `b`, but not both (`git rev-list a...b`)
+    pub fn symmetric_difference(mut self, a: &commit::Hash, b: &commit::Hash) -> Self {
+        self.queue.start(a, LEFT);
+        self.queue.start(b, RIGHT);
+        self
+    }
+
+    /// Include/exclude commits by a `git rev-list` style revision: "rev", "^rev", "a..b", or "a...b".
+    /// An omitted side of a range (e.g. "main..") defaults to "HEAD".  Annotated [Tag]s are peeled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let last    = cache.rev_walk().range("HEAD~1..HEAD").unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    /// assert_eq!(last.len(), 1);
+    /// assert_eq!(last[0].hash, cache.repository.rev_parse_commit("HEAD").unwrap());
+    /// ```
+    pub fn range(self, spec: &str) -> Result<Self, RevParseError> {
+        let side = |rev: &str| -> Result<commit::Hash, RevParseError> {
+            let rev = if rev.is_empty() { "HEAD" } else { rev };
+            self.cache.repository.rev_parse_commit(&format!("{}^{{commit}}", rev))
+        };
+
+        if let Some(i) = spec.find("...") {
+            let (a, b) = (side(&spec[..i])?, side(&spec[i+3..])?);
+            Ok(self.symmetric_difference(&a, &b))
+        } else if let Some(i) = spec.find("..") {
+            let (a, b) = (side(&spec[..i])?, side(&spec[i+2..])?);
+            Ok(self.hide(&a).push(&b))
+        } else if let Some(spec) = spec.strip_prefix('^') {
+            let a = side(spec)?;
+            Ok(self.hide(&a))
+        } else {
+            let a = side(spec)?;
+            Ok(self.push(&a))
+        }
+    }
+
+    /// Only yield commits that changed any of `paths` (files or directories, e.g. "src/foo.rs" or "src"), like `git log -- paths...`.
+    ///
+    /// History is simplified as `git` does by default: a commit matching any parent at `paths` is skipped,
+    /// and for merges, only that parent's history is followed.  An empty list of `paths` doesn't filter anything (like `git log --`.)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let head    = cache.repository.rev_parse_commit("HEAD").unwrap();
+    /// for commit in cache.rev_walk().push(&head).paths(&["Cargo.toml"]).max_count(5) {
+    ///     let commit = commit.unwrap();
+    ///     let parent = commit.parents.first().map(|p| cache.commit(p).unwrap().tree.clone());
+    ///     let changes = cache.diff_trees(parent.as_ref(), Some(&commit.tree), TreeDiffOptions::default()).unwrap();
+    ///     assert!(changes.iter().any(|c| c.path() == "Cargo.toml"));
+    /// }
+    /// ```
+    pub fn paths<P: AsRef<[u8]>>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        let paths = paths.into_iter().map(|p| p.as_ref().to_vec()).collect::<Vec<_>>();
+        self.queue.paths = if paths.is_empty() { None } else { Some(PathFilter { paths, follow: false }) };
+        self
+    }
+
+    /// Like [paths](Self::paths) for a single file, but continue listing history past renames (`git log --follow -- path`)
+    pub fn follow(mut self, path: impl AsRef<[u8]>) -> Self {
+        self.queue.paths = Some(PathFilter { paths: vec![path.as_ref().to_vec()], follow: true });
+        self
+    }
+
+    /// Set the order [Commit]s are yielded in (defaults to [RevWalkOrder::Default])
+    pub fn order(mut self, order: RevWalkOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Yield [Commit]s oldest first (`git rev-list --reverse`).  Applied after [max_count](Self::max_count), like `git`.
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    /// Only follow the first parent of merge commits (`git rev-list --first-parent`).  Exclusions still follow every parent.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let head    = cache.repository.rev_parse_commit("HEAD").unwrap();
+    /// let mut prev : Option<std::sync::Arc<Commit>> = None;
+    /// for commit in cache.rev_walk().push(&head).first_parent(true).max_count(20) {
+    ///     let commit = commit.unwrap();
+    ///     if let Some(prev) = prev { assert_eq!(prev.parents[0], commit.hash); }
+    ///     prev = Some(commit);
+    /// }
+    /// ```
+    pub fn first_parent(mut self, first_parent: bool) -> Self {
+        self.first_parent = first_parent;
+        self
+    }
+
+    /// Stop after yielding `count` [Commit]s (`git rev-list --max-count`)
+    pub fn max_count(mut self, count: usize) -> Self {
+        self.max_count = Some(count);
+        self
+    }
+
+    fn next_unsorted(&mut self) -> io::Result<Option<Arc<Commit>>> {
+        self.queue.next(self.cache, self.first_parent)
+    }
+
+    /// Read every commit the walk will yield, then sort, truncate, and reverse as configured
+    fn sort_all(&mut self) -> io::Result<Vec<Arc<Commit>>> {
+        let mut commits = Vec::new();
+        while let Some(commit) = self.next_unsorted()? {
+            commits.push(commit);
+            if self.order == RevWalkOrder::Default && Some(commits.len()) == self.max_count { break; }
+        }
+
+        if self.order != RevWalkOrder::Default { commits = topo_sort(commits, self.order, self.first_parent); }
+        if let Some(max) = self.max_count { commits.truncate(max); }
+        if self.reverse { commits.reverse(); }
+        Ok(commits)
+    }
+}
+
+impl Iterator for RevWalk<'_> {
+    type Item = io::Result<Arc<Commit>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done { return None; }
+
+        if self.order == RevWalkOrder::Default && !self.reverse {
+            if Some(self.yielded) == self.max_count { return None; }
+            return match self.next_unsorted() {
+                Ok(Some(commit))    => { self.yielded += 1; Some(Ok(commit)) },
+                Ok(None)            => { self.done = true; None },
+                Err(e)              => { self.done = true; Some(Err(e)) },
+            };
+        }
+
+        if self.sorted.is_none() {
+            match self.sort_all() {
+                Ok(commits) => self.sorted = Some(commits.into_iter()),
+                Err(e)      => { self.done = true; return Some(Err(e)); },
+            }
+        }
+        self.sorted.as_mut().unwrap().next().map(Ok)
+    }
+}
+
+
+
+const SEEN          : u8 = 0x01;
+const PROCESSED     : u8 = 0x02;
+const UNINTERESTING : u8 = 0x04;
+const LEFT          : u8 = 0x08;
+const RIGHT         : u8 = 0x10;
+const INHERITED     : u8 = UNINTERESTING | LEFT | RIGHT;
+
+fn is_uninteresting(flags: u8) -> bool { flags & UNINTERESTING != 0 || flags & (LEFT | RIGHT) == (LEFT | RIGHT) }
+
+struct Queued {
+    time:   i64,
+    seq:    Reverse<usize>,
+    commit: Arc<Commit>,
+}
+
+impl PartialEq  for Queued { fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal } }
+impl Eq         for Queued {}
+impl PartialOrd for Queued { fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) } }
+impl Ord        for Queued { fn cmp(&self, other: &Self) -> Ordering { (self.time, self.seq).cmp(&(other.time, other.seq)) } }
+
+/// Commit date ordered queue of commits to visit, and the flags of every commit seen so far
+#[derive(Default)]
+struct Queue {
+    heap:       BinaryHeap<Queued>,
+    flags:      HashMap<commit::Hash, u8>,
+    starts:     Vec<(commit::Hash, u8)>,
+    seq:        usize,
+    paths:      Option<PathFilter>,
+    /// How many commits in `heap` are still interesting (once none are, the walk is over)
+    interesting: usize,
+}
+
+impl Queue {
+    fn start(&mut self, commit: &commit::Hash, flags: u8) {
+        self.starts.push((commit.clone(), flags));
+    }
+
+    fn next(&mut self, cache: &RepositoryCache, first_parent: bool) -> io::Result<Option<Arc<Commit>>> {
+        for (commit, flags) in std::mem::take(&mut self.starts) {
+            self.add(cache, &commit, flags)?;
+        }
+
+        loop {
+            if self.interesting == 0 { return Ok(None); }
+            let commit = self.heap.pop().unwrap().commit;
+            let flags = self.or_flags(&commit.hash, PROCESSED) | PROCESSED;
+            if !is_uninteresting(flags) { self.interesting -= 1; }
+
+            if is_uninteresting(flags) {
+                for parent in commit.parents.iter() { self.add(cache, parent, flags & INHERITED)?; }
+            } else {
+                let parents = if first_parent { &commit.parents[..commit.parents.len().min(1)] } else { &commit.parents[..] };
+                let (show, parents) = match self.paths.as_mut() {
+                    None            => (true, parents.to_vec()),
+                    Some(filter)    => filter.simplify(cache, &commit, parents)?,
+                };
+                for parent in parents.iter() { self.add(cache, parent, flags & INHERITED)?; }
+                if show { return Ok(Some(commit)); }
+            }
+        }
+    }
+
+    fn add(&mut self, cache: &RepositoryCache, commit: &commit::Hash, flags: u8) -> io::Result<()> {
+        match self.flags.get(commit).cloned() {
+            None => {
+                let commit = cache.commit(commit)?;
+                self.flags.insert(commit.hash.clone(), flags | SEEN);
+                if !is_uninteresting(flags) { self.interesting += 1; }
+                self.seq += 1;
+                self.heap.push(Queued { time: commit.committer.time, seq: Reverse(self.seq), commit });
+                Ok(())
+            },
+            Some(old) if old & PROCESSED != 0 && flags & !old != 0 => {
+                // Already visited (commit dates were skewed): push the new flags through it's visited ancestors
+                let mut stack = vec![commit.clone()];
+                while let Some(hash) = stack.pop() {
+                    let old = self.flags[&hash];
+                    if flags & !old == 0 { continue; }
+                    self.or_flags(&hash, flags);
+                    if old & PROCESSED == 0 { continue; }
+                    for parent in cache.commit(&hash)?.parents.iter() {
+                        if self.flags.contains_key(parent) { stack.push(parent.clone()); } else { self.add(cache, parent, flags)?; }
+                    }
+                }
+                Ok(())
+            },
+            Some(_) => {
+                self.or_flags(commit, flags);
+                Ok(())
+            },
+        }
+    }
+
+    /// Add `flags` to an already seen `commit`, keeping [interesting](Self::interesting) up to date.  Returns the old flags.
+    fn or_flags(&mut self, commit: &commit::Hash, flags: u8) -> u8 {
+        let f = self.flags.get_mut(commit).unwrap();
+        let old = *f;
+        *f |= flags;
+        if old & PROCESSED == 0 && !is_uninteresting(old) && is_uninteresting(*f) { self.interesting -= 1; } // still queued, but no longer interesting
+        old
+    }
+}
+
+/// Reorder `commits` so no parent comes before any of it's children, preferring newest commit date ([RevWalkOrder::Date])
+/// or depth first ([RevWalkOrder::Topo]) among the commits available.
+fn topo_sort(commits: Vec<Arc<Commit>>, order: RevWalkOrder, first_parent: bool) -> Vec<Arc<Commit>> {
+    let parents_of = |c: &Commit| -> Vec<commit::Hash> {
+        let n = if first_parent { c.parents.len().min(1) } else { c.parents.len() };
+        c.parents[..n].to_vec()
+    };
+
+    let mut children : HashMap<commit::Hash, usize> = commits.iter().map(|c| (c.hash.clone(), 0)).collect();
+    for commit in commits.iter() {
+        for parent in parents_of(commit) {
+            if let Some(n) = children.get_mut(&parent) { *n += 1; }
+        }
+    }
+    let mut by_hash : HashMap<commit::Hash, Arc<Commit>> = commits.iter().map(|c| (c.hash.clone(), c.clone())).collect();
+
+    let mut sorted  = Vec::with_capacity(commits.len());
+    let mut stack   = Vec::new();          // RevWalkOrder::Topo
+    let mut heap    = BinaryHeap::new();   // RevWalkOrder::Date
+    let mut seq     = 0;
+    let mut ready   = |commit: Arc<Commit>, stack: &mut Vec<Arc<Commit>>, heap: &mut BinaryHeap<Queued>| {
+        seq += 1;
+        match order {
+            RevWalkOrder::Topo  => stack.push(commit),
+            _                   => heap.push(Queued { time: commit.committer.time, seq: Reverse(seq), commit }),
+        }
+    };
+
+    let tips = commits.iter().filter(|c| children[&c.hash] == 0).cloned().collect::<Vec<_>>();
+    if order == RevWalkOrder::Topo {
+        for tip in tips.into_iter().rev() { ready(tip, &mut stack, &mut heap); } // first tip on top of the stack
+    } else {
+        for tip in tips.into_iter() { ready(tip, &mut stack, &mut heap); }
+    }
+
+    loop {
+        let commit = match order {
+            RevWalkOrder::Topo  => stack.pop(),
+            _                   => heap.pop().map(|q| q.commit),
+        };
+        let commit = match commit { Some(c) => c, None => break };
+        for parent in parents_of(&commit) {
+            let n = match children.get_mut(&parent) { Some(n) => n, None => continue };
+            *n -= 1;
+            if *n == 0 { ready(by_hash.remove(&parent).unwrap(), &mut stack, &mut heap); }
+        }
+        sorted.push(commit);
+    }
+    sorted
+}
+
+/// The paths a [RevWalk] is limited to, and the history simplification that goes with them
+struct PathFilter {
+    paths:  Vec<Vec<u8>>,
+    follow: bool,
+}
+
+impl PathFilter {
+    /// Returns if `commit` should be yielded, and which of it's `parents` to walk
+    fn simplify(&mut self, cache: &RepositoryCache, commit: &Commit, parents: &[commit::Hash]) -> io::Result<(bool, Vec<commit::Hash>)> {
+        let here = self.entries(cache, &commit.tree)?;
+        if parents.is_empty() { return Ok((here.iter().any(|e| e.is_some()), Vec::new())); }
+
+        let mut there = Vec::with_capacity(parents.len());
+        for parent in parents.iter() {
+            let parent_tree = &cache.commit(parent)?.tree;
+            let entries = if *parent_tree == commit.tree { here.clone() } else { self.entries(cache, parent_tree)? };
+            if entries == here { return Ok((false, vec![parent.clone()])); } // TREESAME: this parent explains everything
+            there.push(entries);
+        }
+
+        // The followed file appeared out of nowhere: keep following it under it's old name, if it was renamed
+        if self.follow && here[0].is_some() && there.iter().all(|entries| entries[0].is_none()) {
+            let parent_tree = cache.commit(&parents[0])?.tree.clone();
+            if let Some(old_path) = tree_diff::rename_source(cache, &parent_tree, &commit.tree, &self.paths[0], 50)? {
+                self.paths[0] = old_path.as_bytes().unwrap_or_else(|| old_path.as_str_lossy().as_bytes()).to_vec();
+            }
+        }
+
+        Ok((true, parents.to_vec()))
+    }
+
+    /// The (hash, mode) of every path in `tree`, or `None` where a path doesn't exist
+    fn entries(&self, cache: &RepositoryCache, tree: &tree::Hash) -> io::Result<Vec<Option<(unknown::Hash, tree::Mode)>>> {
+        let mut entries = Vec::with_capacity(self.paths.len());
+        for path in self.paths.iter() {
+            let entry = match cache.tree_entry_at(tree, path) {
+                Ok(entry)                               => Some(entry),
+                Err(PathError::NotFound(_))             => None,
+                Err(PathError::NotADirectory(_))        => None,
+                Err(PathError::CrossedSubmodule(sub))   => Some(cache.tree_entry_at(tree, &sub)?), // compare the submodule itself
+                Err(PathError::Io(e))                   => return Err(e),
+            };
+            entries.push(entry.map(|e| (e.hash, e.mode)));
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,158 @@
+use crate::*;
+
+use std::fmt::{self, Debug, Display, Formatter};
+use std::io;
+
+
+
+/// An author, committer, or tagger signature (e.g. "Some Name \<some@email.com\> 1600000000 -0700")
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Signature {
+    /// The name of the person (e.g. "Some Name")
+    pub name:           Name,
+
+    /// The email of the person, without angle brackets (e.g. "some@email.com")
+    pub email:          Name,
+
+    /// Seconds since the unix epoch (1970-01-01 00:00:00 UTC)
+    pub time:           i64,
+
+    /// Timezone offset from UTC, in minutes (e.g. -0700 => -420)
+    pub offset_minutes: i32,
+
+    _nonexhaustive:     (),
+}
+
+impl Signature {
+    /// Parse a signature in git's "Name \<email\> seconds +hhmm" format
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use clgit::*;
+    /// let sig = Signature::parse(b"Some Name <some@email.com> 1600000000 -0730").unwrap();
+    /// assert_eq!(sig.name,            "Some Name");
+    /// assert_eq!(sig.email,           "some@email.com");
+    /// assert_eq!(sig.time,            1600000000);
+    /// assert_eq!(sig.offset_minutes,  -450);
+    /// assert_eq!(sig.to_string(),     "Some Name <some@email.com> 1600000000 -0730");
+    /// ```
+    pub fn parse(raw: &[u8]) -> io::Result<Self> {
+        let bad = || io::Error::new(io::ErrorKind::InvalidData, format!("malformed signature {:?}", String::from_utf8_lossy(raw)));
+
+        let lt = raw.iter().position(|b| *b == b'<').ok_or_else(bad)?;
+        let gt = raw.iter().rposition(|b| *b == b'>').ok_or_else(bad)?;
+        if gt < lt { return Err(bad()); }
+
+        let name    = trim(&raw[..lt]);
+        let email   = &raw[lt+1..gt];
+        let when    = std::str::from_utf8(&raw[gt+1..]).map_err(|_| bad())?;
+
+        let mut when = when.split_whitespace();
+        let time    = when.next().and_then(|t| t.parse().ok()).unwrap_or(0);
+        let offset  = when.next().and_then(parse_offset).unwrap_or(0);
+
+        Ok(Self {
+            name:           Name::from(name),
+            email:          Name::from(email),
+            time,
+            offset_minutes: offset,
+            _nonexhaustive: (),
+        })
+    }
+
+    /// Like [parse](Self::parse), but never fails, like git when showing old or hand-crafted objects:
+    /// without "\<email\>" the whole signature becomes the name, and missing or malformed times become 0.
+    pub(crate) fn parse_lenient(raw: &[u8]) -> Self {
+        Self::parse(raw).unwrap_or_else(|_| Self {
+            name:           Name::from(trim(raw)),
+            email:          Name::default(),
+            time:           0,
+            offset_minutes: 0,
+            _nonexhaustive: (),
+        })
+    }
+}
+
+impl Display for Signature {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let sign = if self.offset_minutes < 0 { '-' } else { '+' };
+        let offset = self.offset_minutes.abs();
+        write!(fmt, "{} <{}> {} {}{:02}{:02}", self.name.as_str_lossy(), self.email.as_str_lossy(), self.time, sign, offset / 60, offset % 60)
+    }
+}
+
+impl Debug for Signature {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "Signature({:?})", self.to_string())
+    }
+}
+
+
+
+fn trim(s: &[u8]) -> &[u8] {
+    let start   = s.iter().position (|b| !b.is_ascii_whitespace()).unwrap_or(s.len());
+    let end     = s.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(start, |e| e+1);
+    &s[start..end]
+}
+
+/// Parse "+hhmm" / "-hhmm" into minutes
+fn parse_offset(tz: &str) -> Option<i32> {
+    let (sign, digits) = match tz.as_bytes().first()? {
+        b'+'    => (1, &tz[1..]),
+        b'-'    => (-1, &tz[1..]),
+        _       => return None,
+    };
+    if digits.len() != 4 { return None; }
+    let hhmm : i32 = digits.parse().ok()?;
+    Some(sign * ((hhmm / 100) * 60 + (hhmm % 100)))
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    #[test] fn parse_offsets() {
+        assert_eq!(Signature::parse(b"A <a@b> 0 +0000").unwrap().offset_minutes, 0);
+        assert_eq!(Signature::parse(b"A <a@b> 0 +0530").unwrap().offset_minutes, 330);
+        assert_eq!(Signature::parse(b"A <a@b> 0 -1200").unwrap().offset_minutes, -720);
+        assert_eq!(Signature::parse(b"A <a@b> 0 0530" ).unwrap().offset_minutes, 0);
+        assert_eq!(Signature::parse(b"A <a@b> 0 +53"  ).unwrap().offset_minutes, 0);
+    }
+
+    #[test] fn parse_odd_but_valid() {
+        let sig = Signature::parse(b"  Spaced   Out  <weird <email>>  1600000000   +0100 ").unwrap();
+        assert_eq!(sig.name,    "Spaced   Out");
+        assert_eq!(sig.email,   "weird <email>");
+        assert_eq!(sig.time,    1600000000);
+
+        let sig = Signature::parse(b"No Time <a@b>").unwrap();
+        assert_eq!((sig.time, sig.offset_minutes), (0, 0));
+
+        let sig = Signature::parse(b"<a@b> -5 +0000").unwrap();
+        assert_eq!(sig.name, "");
+        assert_eq!(sig.time, -5);
+
+        let sig = Signature::parse(b"Caf\xe9 <a@b> 1 +0000").unwrap();
+        assert_eq!(sig.name.as_bytes(), Some(&b"Caf\xe9"[..]));
+    }
+
+    #[test] fn parse_malformed() {
+        for raw in [&b"No Email 1600000000 +0000"[..], b"Backwards >a@b< 1 +0000", b"Open <a@b 1 +0000", b""].iter() {
+            assert_eq!(Signature::parse(raw).err().map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+        }
+        assert_eq!(Signature::parse(b"A <a@b> \xff +0000").err().map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+    }
+
+    #[test] fn parse_lenient() {
+        let sig = Signature::parse_lenient(b" No Email 1600000000 +0000 ");
+        assert_eq!(sig.name,    "No Email 1600000000 +0000");
+        assert_eq!(sig.email,   "");
+        assert_eq!(sig.time,    0);
+
+        let sig = Signature::parse_lenient(b"");
+        assert_eq!((sig.name.as_str(), sig.email.as_str(), sig.time), (Some(""), Some(""), 0));
+
+        assert_eq!(Signature::parse_lenient(b"A <a@b> 5 +0100"), Signature::parse(b"A <a@b> 5 +0100").unwrap());
+    }
+}
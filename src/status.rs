@@ -0,0 +1,390 @@
+use crate::*;
+use crate::packed_refs::os_string;
+
+use std::ffi::OsString;
+use std::io;
+
+
+
+/// Options for [Repository::status]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatusOptions {
+    /// List every untracked file (`--untracked-files=all`).  Otherwise, untracked files aren't listed at all (`--untracked-files=no`).
+    pub untracked:  bool,
+
+    /// List ignored files (`--ignored`)
+    pub ignored:    bool,
+}
+
+/// The state of a work tree and index, as reported by [Repository::status] (`git status --porcelain=v2 --branch -z`)
+#[derive(Clone, Debug)]
+pub struct Status {
+    /// The current branch, upstream, etc.
+    pub branch:     StatusBranch,
+
+    /// Every changed, unmerged, untracked, or ignored path
+    pub entries:    Vec<StatusEntry>,
+
+    _nonexhaustive: (),
+}
+
+/// The "# branch.*" headers of `git status --porcelain=v2 --branch`
+#[derive(Clone, Debug, Default)]
+pub struct StatusBranch {
+    /// The [Commit] HEAD points to, or `None` before the initial commit
+    pub commit:         Option<commit::Hash>,
+
+    /// The checked out branch (e.g. "master"), or `None` if HEAD is detached
+    pub head:           Option<OsString>,
+
+    /// The upstream branch (e.g. "origin/master"), if any
+    pub upstream:       Option<OsString>,
+
+    /// How many commits HEAD is (ahead, behind) it's upstream, if known
+    pub ahead_behind:   Option<(usize, usize)>,
+
+    _nonexhaustive:     (),
+}
+
+/// One line of `git status --porcelain=v2` output
+#[derive(Clone, Debug)]
+pub enum StatusEntry {
+    /// A changed path ("1 ...")
+    Changed(StatusChange),
+
+    /// A renamed or copied path ("2 ...")
+    Renamed {
+        /// The change, including the new path
+        change:         StatusChange,
+        /// The path this was renamed or copied from
+        original_path:  Name,
+        /// `true` for copies, `false` for renames
+        copied:         bool,
+        /// How similar the original and new contents are (0 ..= 100 percent)
+        score:          u8,
+    },
+
+    /// A path with merge conflicts ("u ...")
+    Unmerged(StatusUnmerged),
+
+    /// An untracked path ("? ...")
+    Untracked(Name),
+
+    /// An ignored path ("! ...")
+    Ignored(Name),
+}
+
+impl StatusEntry {
+    /// The (new) path of this entry
+    pub fn path(&self) -> &Name {
+        match self {
+            StatusEntry::Changed(change)            => &change.path,
+            StatusEntry::Renamed { change, .. }     => &change.path,
+            StatusEntry::Unmerged(unmerged)         => &unmerged.path,
+            StatusEntry::Untracked(path)            => path,
+            StatusEntry::Ignored(path)              => path,
+        }
+    }
+}
+
+/// The staged/unstaged state of one side of a status entry (one character of `git status`'s "XY" field)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatusCode {
+    /// "." - no change
+    Unmodified,
+    /// "M"
+    Modified,
+    /// "T" - e.g. a file became a symlink
+    TypeChanged,
+    /// "A"
+    Added,
+    /// "D"
+    Deleted,
+    /// "R"
+    Renamed,
+    /// "C"
+    Copied,
+    /// "U" - updated but unmerged
+    Unmerged,
+}
+
+impl StatusCode {
+    fn parse(code: u8) -> Option<Self> {
+        Some(match code {
+            b'.' => StatusCode::Unmodified,
+            b'M' => StatusCode::Modified,
+            b'T' => StatusCode::TypeChanged,
+            b'A' => StatusCode::Added,
+            b'D' => StatusCode::Deleted,
+            b'R' => StatusCode::Renamed,
+            b'C' => StatusCode::Copied,
+            b'U' => StatusCode::Unmerged,
+            _    => return None,
+        })
+    }
+}
+
+/// The state of a submodule path ("S\<c\>\<m\>\<u\>")
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubmoduleStatus {
+    /// The submodule's checked out commit changed
+    pub commit_changed:     bool,
+    /// The submodule has tracked changes
+    pub modified:           bool,
+    /// The submodule has untracked changes
+    pub untracked:          bool,
+}
+
+/// A changed (or renamed/copied) path
+#[derive(Clone, Debug)]
+pub struct StatusChange {
+    /// The path relative to the root of the work tree
+    pub path:           Name,
+    /// Changes between HEAD and the index
+    pub staged:         StatusCode,
+    /// Changes between the index and the work tree
+    pub unstaged:       StatusCode,
+    /// `Some` if this path is a submodule
+    pub submodule:      Option<SubmoduleStatus>,
+    /// The [Mode](tree::Mode) in HEAD
+    pub head_mode:      tree::Mode,
+    /// The [Mode](tree::Mode) in the index
+    pub index_mode:     tree::Mode,
+    /// The [Mode](tree::Mode) in the work tree
+    pub worktree_mode:  tree::Mode,
+    /// The object in HEAD (all zeros if absent)
+    pub head_hash:      unknown::Hash,
+    /// The object in the index (all zeros if absent)
+    pub index_hash:     unknown::Hash,
+    _nonexhaustive:     (),
+}
+
+/// A path with merge conflicts
+#[derive(Clone, Debug)]
+pub struct StatusUnmerged {
+    /// The path relative to the root of the work tree
+    pub path:           Name,
+    /// "Our" side of the conflict (e.g. `UU`'s first "U", `AA`'s first "A")
+    pub ours:           StatusCode,
+    /// "Their" side of the conflict
+    pub theirs:         StatusCode,
+    /// `Some` if this path is a submodule
+    pub submodule:      Option<SubmoduleStatus>,
+    /// The [Mode](tree::Mode)s of index stages 1 (common ancestor), 2 (ours), and 3 (theirs)
+    pub stage_modes:    [tree::Mode; 3],
+    /// The [Mode](tree::Mode) in the work tree
+    pub worktree_mode:  tree::Mode,
+    /// The objects of index stages 1 (common ancestor), 2 (ours), and 3 (theirs) (all zeros if absent)
+    pub stage_hashes:   [unknown::Hash; 3],
+    _nonexhaustive:     (),
+}
+
+
+
+/// Parse the output of `git status --porcelain=v2 --branch -z`
+pub(crate) fn parse_status(output: &[u8]) -> io::Result<Status> {
+    let mut status = Status { branch: StatusBranch::default(), entries: Vec::new(), _nonexhaustive: () };
+    let mut records = output.split(|b| *b == 0).filter(|r| !r.is_empty());
+
+    while let Some(record) = records.next() {
+        let bad = || io::Error::new(io::ErrorKind::InvalidData, format!("git status --porcelain=v2 returned unexpected record {:?}", String::from_utf8_lossy(record)));
+        let (kind, rest) = match record.iter().position(|b| *b == b' ') {
+            Some(sp)    => (&record[..sp], &record[sp+1..]),
+            None        => return Err(bad()),
+        };
+
+        match kind {
+            b"#"    => parse_header(&mut status.branch, rest).ok_or_else(bad)?,
+            b"1"    => {
+                let (change, _) = parse_change(rest, 7).ok_or_else(bad)?;
+                status.entries.push(StatusEntry::Changed(change));
+            },
+            b"2"    => {
+                let (change, score) = parse_change(rest, 8).ok_or_else(bad)?;
+                let score = score.ok_or_else(bad)?;
+                let copied = match score.first() { Some(b'R') => false, Some(b'C') => true, _ => return Err(bad()) };
+                let score = std::str::from_utf8(&score[1..]).ok().and_then(|s| s.parse().ok()).ok_or_else(bad)?;
+                let original_path = Name::from(records.next().ok_or_else(bad)?);
+                status.entries.push(StatusEntry::Renamed { change, original_path, copied, score });
+            },
+            b"u"    => status.entries.push(StatusEntry::Unmerged(parse_unmerged(rest).ok_or_else(bad)?)),
+            b"?"    => status.entries.push(StatusEntry::Untracked(Name::from(rest))),
+            b"!"    => status.entries.push(StatusEntry::Ignored(Name::from(rest))),
+            _       => return Err(bad()),
+        }
+    }
+
+    Ok(status)
+}
+
+fn parse_header(branch: &mut StatusBranch, header: &[u8]) -> Option<()> {
+    let sp = header.iter().position(|b| *b == b' ').unwrap_or(header.len());
+    let (key, value) = (&header[..sp], header.get(sp+1..).unwrap_or(b""));
+    match key {
+        b"branch.oid"       => branch.commit    = if value == b"(initial)" { None } else { Some(commit::Hash::from_str(std::str::from_utf8(value).ok()?).ok()?) },
+        b"branch.head"      => branch.head      = if value == b"(detached)" { None } else { Some(os_string(value)) }, // branch names needn't be UTF-8
+        b"branch.upstream"  => branch.upstream  = Some(os_string(value)),
+        b"branch.ab"        => {
+            let mut ab = std::str::from_utf8(value).ok()?.split(' ');
+            let ahead   = ab.next()?.trim_start_matches('+').parse().ok()?;
+            let behind  = ab.next()?.trim_start_matches('-').parse().ok()?;
+            branch.ahead_behind = Some((ahead, behind));
+        },
+        _                   => {}, // e.g. "# stash <N>"
+    }
+    Some(())
+}
+
+/// Split off `n` space separated fields, leaving the (possibly space containing) path as the last field
+fn split_fields(record: &[u8], n: usize) -> Option<Vec<&[u8]>> {
+    let fields = record.splitn(n+1, |b| *b == b' ').collect::<Vec<_>>();
+    if fields.len() != n+1 { return None; }
+    Some(fields)
+}
+
+fn parse_xy(xy: &[u8]) -> Option<(StatusCode, StatusCode)> {
+    if xy.len() != 2 { return None; }
+    Some((StatusCode::parse(xy[0])?, StatusCode::parse(xy[1])?))
+}
+
+fn parse_submodule(sub: &[u8]) -> Option<Option<SubmoduleStatus>> {
+    match sub {
+        b"N..." => Some(None),
+        [b'S', c, m, u] => Some(Some(SubmoduleStatus { commit_changed: *c == b'C', modified: *m == b'M', untracked: *u == b'U' })),
+        _ => None,
+    }
+}
+
+fn parse_hash(hash: &[u8]) -> Option<unknown::Hash> {
+    unknown::Hash::from_str(std::str::from_utf8(hash).ok()?).ok()
+}
+
+/// Parse the fields after "1 " (`n` = 7) or "2 " (`n` = 8, returning the extra "\<X\>\<score\>" field)
+fn parse_change(record: &[u8], n: usize) -> Option<(StatusChange, Option<&[u8]>)> {
+    let f = split_fields(record, n)?;
+    let (staged, unstaged) = parse_xy(f[0])?;
+    let change = StatusChange {
+        staged,
+        unstaged,
+        submodule:      parse_submodule(f[1])?,
+        head_mode:      tree::Mode::parse(f[2])?,
+        index_mode:     tree::Mode::parse(f[3])?,
+        worktree_mode:  tree::Mode::parse(f[4])?,
+        head_hash:      parse_hash(f[5])?,
+        index_hash:     parse_hash(f[6])?,
+        path:           Name::from(f[n]),
+        _nonexhaustive: (),
+    };
+    Some((change, if n > 7 { Some(f[7]) } else { None }))
+}
+
+fn parse_unmerged(record: &[u8]) -> Option<StatusUnmerged> {
+    let f = split_fields(record, 9)?;
+    let (ours, theirs) = parse_xy(f[0])?;
+    Some(StatusUnmerged {
+        path:           Name::from(f[9]),
+        ours,
+        theirs,
+        submodule:      parse_submodule(f[1])?,
+        stage_modes:    [tree::Mode::parse(f[2])?, tree::Mode::parse(f[3])?, tree::Mode::parse(f[4])?],
+        worktree_mode:  tree::Mode::parse(f[5])?,
+        stage_hashes:   [parse_hash(f[6])?, parse_hash(f[7])?, parse_hash(f[8])?],
+        _nonexhaustive: (),
+    })
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    const A : &str = "1111111111111111111111111111111111111111";
+    const B : &str = "2222222222222222222222222222222222222222";
+    const Z : &str = "0000000000000000000000000000000000000000";
+
+    #[test] fn parse_branch_headers() {
+        let output = format!("# branch.oid {}\0# branch.head main\0# branch.upstream origin/main\0# branch.ab +3 -14\0# stash 2\0", A);
+        let branch = parse_status(output.as_bytes()).unwrap().branch;
+        assert_eq!(branch.commit.unwrap().to_string(), A);
+        assert_eq!(branch.head.as_deref(),      Some(OsStr::new("main")));
+        assert_eq!(branch.upstream.as_deref(),  Some(OsStr::new("origin/main")));
+        assert_eq!(branch.ahead_behind,         Some((3, 14)));
+
+        let branch = parse_status(b"# branch.oid (initial)\0# branch.head (detached)\0").unwrap().branch;
+        assert!(branch.commit.is_none());
+        assert!(branch.head.is_none());
+        assert!(branch.upstream.is_none());
+        assert!(branch.ahead_behind.is_none());
+    }
+
+    #[cfg(unix)] #[test] fn parse_non_utf8_branch() {
+        use std::os::unix::ffi::OsStrExt;
+        let branch = parse_status(b"# branch.head caf\xe9\0").unwrap().branch;
+        assert_eq!(branch.head.unwrap().as_bytes(), b"caf\xe9");
+    }
+
+    #[test] fn parse_entries() {
+        let output = format!(
+            "1 .M N... 100644 100644 100644 {a} {a} src/lib.rs\0\
+             1 A. SC.U 000000 160000 160000 {z} {b} sub module\0\
+             2 R. N... 100644 100644 100644 {a} {a} R100 new name\0old name\0\
+             u UU N... 100644 100644 100644 100755 {a} {b} {a} conflicted\0\
+             ? untracked file\0\
+             ! target/\0",
+            a = A, b = B, z = Z);
+        let entries = parse_status(output.as_bytes()).unwrap().entries;
+        assert_eq!(entries.len(), 6);
+
+        match &entries[0] {
+            StatusEntry::Changed(change) => {
+                assert_eq!(change.path,                                 "src/lib.rs");
+                assert_eq!((change.staged, change.unstaged),            (StatusCode::Unmodified, StatusCode::Modified));
+                assert!(change.submodule.is_none());
+                assert_eq!(change.worktree_mode,                        tree::Mode::Regular);
+                assert_eq!(change.index_hash.to_string(),               A);
+            },
+            other => panic!("expected Changed, got {:?}", other),
+        }
+        match &entries[1] {
+            StatusEntry::Changed(change) => {
+                assert_eq!(change.path,                                 "sub module");
+                assert_eq!(change.submodule,                            Some(SubmoduleStatus { commit_changed: true, modified: false, untracked: true }));
+                assert_eq!(change.index_mode,                           tree::Mode::Gitlink);
+                assert_eq!(change.head_hash.to_string(),                Z);
+            },
+            other => panic!("expected Changed, got {:?}", other),
+        }
+        match &entries[2] {
+            StatusEntry::Renamed { change, original_path, copied, score } => {
+                assert_eq!(change.path,                                 "new name");
+                assert_eq!(*original_path,                              "old name");
+                assert_eq!((*copied, *score),                           (false, 100));
+            },
+            other => panic!("expected Renamed, got {:?}", other),
+        }
+        match &entries[3] {
+            StatusEntry::Unmerged(unmerged) => {
+                assert_eq!(unmerged.path,                               "conflicted");
+                assert_eq!((unmerged.ours, unmerged.theirs),            (StatusCode::Unmerged, StatusCode::Unmerged));
+                assert_eq!(unmerged.worktree_mode,                      tree::Mode::Executable);
+                assert_eq!(unmerged.stage_hashes[1].to_string(),        B);
+            },
+            other => panic!("expected Unmerged, got {:?}", other),
+        }
+        assert_eq!(*entries[4].path(), "untracked file");
+        assert!(matches!(entries[4], StatusEntry::Untracked(_)));
+        assert_eq!(*entries[5].path(), "target/");
+        assert!(matches!(entries[5], StatusEntry::Ignored(_)));
+    }
+
+    #[test] fn parse_malformed() {
+        let bad = |output: &str| parse_status(output.as_bytes()).unwrap_err().kind();
+        assert_eq!(bad("# branch.oid nothex\0"),                            io::ErrorKind::InvalidData);
+        assert_eq!(bad("# branch.ab +x -1\0"),                              io::ErrorKind::InvalidData);
+        assert_eq!(bad("1 .M N... 100644 100644 100644 path\0"),            io::ErrorKind::InvalidData);
+        assert_eq!(bad(&format!("2 R. N... 100644 100644 100644 {a} {a} R100 new\0", a = A)), io::ErrorKind::InvalidData); // missing original path
+        assert_eq!(bad("X what\0"),                                         io::ErrorKind::InvalidData);
+        assert_eq!(bad("nospace\0"),                                        io::ErrorKind::InvalidData);
+    }
+}
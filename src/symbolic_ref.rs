@@ -0,0 +1,65 @@
+use crate::*;
+
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Debug, Formatter};
+
+
+
+/// What a single ref file (e.g. "HEAD" or "refs/remotes/origin/HEAD") contains, without following any symbolic refs
+#[derive(Clone, PartialEq, Eq)]
+pub enum RefTarget {
+    /// A symbolic ref to another ref (e.g. "ref: refs/heads/master" => "refs/heads/master")
+    Symbolic(OsString),
+
+    /// A direct reference to an object (typically a [Commit])
+    Direct(unknown::Hash),
+}
+
+impl Debug for RefTarget {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            RefTarget::Symbolic(name)   => write!(fmt, "RefTarget::Symbolic({:?})", name),
+            RefTarget::Direct(hash)     => write!(fmt, "RefTarget::Direct(\"{}\")", hash),
+        }
+    }
+}
+
+
+
+/// Where `HEAD` points
+#[derive(Clone, PartialEq, Eq)]
+pub enum Head {
+    /// `HEAD` is a symbolic ref to a branch (e.g. "refs/heads/master").
+    /// The branch might not exist yet, e.g. in a freshly `git init`ed repository.
+    Branch(OsString),
+
+    /// `HEAD` is detached, referencing a [Commit] directly
+    Detached(commit::Hash),
+}
+
+impl Head {
+    /// The full name of the branch `HEAD` points to (e.g. "refs/heads/master"), unless detached
+    pub fn branch(&self) -> Option<&OsStr> {
+        match self {
+            Head::Branch(name)  => Some(name),
+            Head::Detached(_)   => None,
+        }
+    }
+
+    /// `true` if `HEAD` references a [Commit] directly instead of a branch
+    pub fn is_detached(&self) -> bool {
+        match self {
+            Head::Branch(_)     => false,
+            Head::Detached(_)   => true,
+        }
+    }
+}
+
+impl Debug for Head {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            Head::Branch(name)      => write!(fmt, "Head::Branch({:?})", name),
+            Head::Detached(hash)    => write!(fmt, "Head::Detached(\"{}\")", hash),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! [Hash](tag::Hash), [Tag], [Ref](tag::Ref)
+
+use crate::*;
+
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, Read};
+
+
+
+/// A [Hash](generic::Hash) referencing an annotated [Tag]
+pub type Hash = generic::Hash<Tag>;
+
+/// A parsed, annotated git tag object.
+///
+/// Lightweight tags are plain refs to another object, and have no [Tag] object.
+#[derive(Debug)]
+pub struct Tag {
+    /// The [Hash](tag::Hash) representing this [Tag]
+    pub hash:           tag::Hash,
+
+    /// The [Hash](unknown::Hash) of the object this [Tag] points to (typically a [Commit], but possibly another [Tag], a [Tree], or a Blob)
+    pub target:         unknown::Hash,
+
+    /// The [FileType] of [target](Self::target)
+    pub target_type:    FileType,
+
+    /// The name of this [Tag] (e.g. "v1.2.3")
+    pub name:           Name,
+
+    /// Who created this [Tag], and when.  Very old tags lack this.
+    pub tagger:         Option<Signature>,
+
+    /// The tag message (including any trailing signature), exactly as stored.  See [message](Self::message) to decode it.
+    pub raw_message:    Vec<u8>,
+
+    _nonexhaustive:     (),
+}
+
+impl Tag {
+    /// [Read] a local [Tag] from a given [Repository]
+    ///
+    /// [Read]:         std::io::Read
+    pub fn read(repository: &Repository, hash: &tag::Hash) -> io::Result<Self> {
+        let mut data = Vec::new();
+        repository.cat_file_tag(hash)?.read_to_end(&mut data)?;
+
+        let (headers, message) = parse_object_headers(&data[..]);
+
+        let mut target      : Option<unknown::Hash> = None;
+        let mut target_type : Option<FileType>      = None;
+        let mut name        : Option<Name>          = None;
+        let mut tagger      : Option<Signature>     = None;
+
+        for (key, value) in headers {
+            let utf8 = || std::str::from_utf8(&value[..]).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("non-utf8 {} header in tag {}", String::from_utf8_lossy(&key), hash)));
+            match &key[..] {
+                b"object"   => target       = Some(unknown::Hash::from_str(utf8()?)?),
+                b"type"     => target_type  = Some(FileType::from(utf8()?)),
+                b"tag"      => name         = Some(Name::from(value)),
+                b"tagger"   => tagger       = Some(Signature::parse_lenient(&value[..])),
+                _other      => {},
+            }
+        }
+
+        let missing = |header: &str| io::Error::new(io::ErrorKind::InvalidData, format!("tag {} missing {:?} header", hash, header));
+        Ok(Self {
+            hash:           hash.clone(),
+            target:         target      .ok_or_else(|| missing("object"))?,
+            target_type:    target_type .ok_or_else(|| missing("type"))?,
+            name:           name        .ok_or_else(|| missing("tag"))?,
+            tagger,
+            raw_message:    message.to_vec(),
+            _nonexhaustive: (),
+        })
+    }
+
+    /// The tag message, decoded as UTF-8 (invalid sequences are replaced with U+FFFD)
+    pub fn message(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.raw_message[..])
+    }
+}
+
+
+/// Follow a chain of annotated [Tag]s starting at `hash` (of type `ty`) to the first non-[Tag] object, reading each [Tag]'s (target_type, target) with `read_tag`
+pub(crate) fn peel(mut ty: FileType, mut hash: unknown::Hash, mut read_tag: impl FnMut(&tag::Hash) -> io::Result<(FileType, unknown::Hash)>) -> io::Result<(FileType, unknown::Hash)> {
+    let start = hash.clone();
+    for _ in 0..100 {
+        if ty != FileType::Tag { return Ok((ty, hash)); }
+        let (next_ty, next) = read_tag(&hash.cast())?;
+        ty      = next_ty;
+        hash    = next;
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, format!("tag {} nested too deeply", start)))
+}
+
+
+
+/// A named reference to a tag (e.g. "v1.0" => "074d881e29cc3bff82da905adcde2aea7cb5b165"), lightweight or annotated
+pub struct Ref {
+    pub(crate) name:        OsString,
+    pub(crate) target:      unknown::Hash,
+    pub(crate) annotated:   bool,
+    pub(crate) commit:      Option<commit::Hash>,
+}
+
+impl Ref {
+    /// Name of the tag (e.g. "v1.0")
+    pub fn name(&self) -> &OsStr { &self.name }
+
+    /// [Hash](unknown::Hash) the tag ref points to directly: a [Tag] object for annotated tags, typically a [Commit] for lightweight tags
+    pub fn target(&self) -> &unknown::Hash { &self.target }
+
+    /// `true` if [target](Self::target) is an annotated [Tag] object
+    pub fn is_annotated(&self) -> bool { self.annotated }
+
+    /// The [Hash](tag::Hash) of the annotated [Tag] object, if any
+    pub fn tag(&self) -> Option<tag::Hash> { if self.annotated { Some(self.target.cast()) } else { None } }
+
+    /// [Hash](commit::Hash) of the [Commit] this tag ultimately points to, after peeling any annotated [Tag]s.
+    /// `None` if the tag points to a [Tree] or Blob instead.
+    pub fn commit(&self) -> Option<&commit::Hash> { self.commit.as_ref() }
+}
+
+impl Debug for Ref {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self.commit.as_ref() {
+            Some(commit) if self.annotated  => write!(fmt, "tag::Ref({:?} => \"{}\" => \"{}\")", self.name, self.target, commit),
+            _                               => write!(fmt, "tag::Ref({:?} => \"{}\")", self.name, self.target),
+        }
+    }
+}
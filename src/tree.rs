@@ -1,10 +1,11 @@
-//! [Hash](tree::Hash), [Tree]
+//! [Hash](tree::Hash), [Tree], [Entry], [Mode]
 
 #![allow(dead_code)] // XXX
 
 use crate::*;
 
 use std::collections::*;
+use std::fmt::{self, Display, Formatter};
 use std::io::{self, BufRead, BufReader};
 use std::str::FromStr;
 
@@ -59,6 +60,7 @@ impl Tree {
             let read = reader.read_until(b' ', &mut permissions)?;
             if read == 0 { break }
             if permissions.pop() != Some(b' ') { return Err(io::Error::new(io::ErrorKind::InvalidData, "file permissions not space terminated in tree")); }
+            let mode = Mode::parse(&permissions[..]).unwrap_or(Mode::Unknown(0)); // garbage modes are still readable via permissions, like git
             let permissions = Permissions(Name::from(permissions));
             
             let mut name = Vec::new();
@@ -70,6 +72,7 @@ impl Tree {
 
             out.entries.insert(name.clone(), Entry {
                 permissions,
+                mode,
                 hash,
                 name,
                 _non_exhaustive: ()
@@ -81,12 +84,15 @@ impl Tree {
 
 
 
-/// A [Tree] entry (e.g. { [Permissions], [Hash](unknown::Hash), [Name], .. })
-#[derive(Debug)]
+/// A [Tree] entry (e.g. { [Permissions], [Mode], [Hash](unknown::Hash), [Name], .. })
+#[derive(Clone, Debug)]
 pub struct Entry {
     /// [Permissions] for a given file or directory (typically "100644" for files or "040000" for trees)
     pub permissions:    Permissions,
 
+    /// The typed [Mode] parsed from [permissions](Self::permissions), or `Mode::Unknown(0)` if they aren't valid octal
+    pub mode:           Mode,
+
     /// A [Hash](unknown::Hash) referencing the contents of [Tree] or Blob
     pub hash:           unknown::Hash,
 
@@ -96,12 +102,115 @@ pub struct Entry {
     _non_exhaustive:    ()
 }
 
+impl Entry {
+    /// The [Hash](tree::Hash) of this entry, if it's a [Tree] (subdirectory)
+    pub fn tree_hash(&self) -> Option<tree::Hash> { if self.mode.is_tree() { Some(self.hash.cast()) } else { None } }
+
+    /// The [Hash](blob::Hash) of this entry, if it's a Blob (regular file, executable, or symlink)
+    pub fn blob_hash(&self) -> Option<blob::Hash> { if self.mode.is_blob() { Some(self.hash.cast()) } else { None } }
+
+    /// The [Hash](commit::Hash) of this entry, if it's a submodule (the [Commit] lives in another repository!)
+    pub fn commit_hash(&self) -> Option<commit::Hash> { if self.mode.is_submodule() { Some(self.hash.cast()) } else { None } }
+}
+
+
+
+/// The type of a [Tree] [Entry], per it's octal mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Mode {
+    /// A regular file Blob (`100644`)
+    Regular,
+
+    /// An executable file Blob (`100755`)
+    Executable,
+
+    /// A symbolic link, stored as a Blob of the link target (`120000`)
+    Symlink,
+
+    /// A subdirectory [Tree] (`040000`)
+    Tree,
+
+    /// A submodule [Commit] (`160000`)
+    Gitlink,
+
+    /// Any other mode, such as the `100664` some ancient versions of git wrote
+    Unknown(u32),
+}
+
+impl Mode {
+    /// Parse an octal mode (e.g. b"100644", b"40000")
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use clgit::tree::Mode;
+    /// assert_eq!(Mode::parse(b"100644"),  Some(Mode::Regular));
+    /// assert_eq!(Mode::parse(b"40000"),   Some(Mode::Tree));
+    /// assert_eq!(Mode::parse(b"160000"),  Some(Mode::Gitlink));
+    /// assert_eq!(Mode::parse(b"100664"),  Some(Mode::Unknown(0o100664)));
+    /// assert_eq!(Mode::parse(b"100668"),  None);
+    /// assert!(Mode::parse(b"100664").unwrap().is_blob());
+    /// ```
+    pub fn parse(octal: &[u8]) -> Option<Self> {
+        if octal.is_empty() || octal.len() > 7 { return None; }
+        let mut raw = 0u32;
+        for digit in octal {
+            match digit {
+                b'0' ..= b'7'   => raw = (raw << 3) | u32::from(digit - b'0'),
+                _               => return None,
+            }
+        }
+        Some(Self::from_raw(raw))
+    }
+
+    /// Convert from a raw numeric mode (e.g. `0o100644`)
+    pub fn from_raw(raw: u32) -> Self {
+        match raw {
+            0o100644    => Mode::Regular,
+            0o100755    => Mode::Executable,
+            0o120000    => Mode::Symlink,
+            0o040000    => Mode::Tree,
+            0o160000    => Mode::Gitlink,
+            other       => Mode::Unknown(other),
+        }
+    }
+
+    /// Convert to a raw numeric mode (e.g. `0o100644`)
+    pub fn as_raw(&self) -> u32 {
+        match *self {
+            Mode::Regular       => 0o100644,
+            Mode::Executable    => 0o100755,
+            Mode::Symlink       => 0o120000,
+            Mode::Tree          => 0o040000,
+            Mode::Gitlink       => 0o160000,
+            Mode::Unknown(raw)  => raw,
+        }
+    }
+
+    /// `true` if this references a subdirectory [Tree]
+    pub fn is_tree(&self) -> bool { (self.as_raw() & 0o170000) == 0o040000 }
+
+    /// `true` if this references a Blob (regular file, executable, or symlink)
+    pub fn is_blob(&self) -> bool { let ty = self.as_raw() & 0o170000; ty == 0o100000 || ty == 0o120000 }
+
+    /// `true` if this references a submodule [Commit]
+    pub fn is_submodule(&self) -> bool { (self.as_raw() & 0o170000) == 0o160000 }
+}
+
+impl Display for Mode {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result { write!(fmt, "{:06o}", self.as_raw()) }
+}
+
 
 
 /// [Tree] [Entry] permissions (typically "100644" for files or "040000" for trees)
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Permissions(Name);
 
+impl Permissions {
+    /// Parse these permissions as a typed [Mode], if valid octal
+    pub fn mode(&self) -> Option<Mode> { Mode::parse(self.0.as_bytes()?) }
+}
+
 impl FromStr for Permissions {
     type Err = io::Error;
     fn from_str(s: &str) -> io::Result<Self> { Ok(Self(Name::from(s))) }
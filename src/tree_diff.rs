@@ -0,0 +1,269 @@
+use crate::*;
+use crate::tree_walk::join_path;
+
+use std::collections::*;
+use std::io::{self, Read};
+use std::sync::Arc;
+
+
+
+/// Options for [RepositoryCache::diff_trees]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TreeDiffOptions {
+    /// Pair up deleted and added blobs as [Renamed](TreeChange::Renamed) if they're at least this similar (0 ..= 100 percent).
+    /// `Some(100)` only detects exact renames, which doesn't require reading any blobs.  `None` disables rename detection.
+    pub rename_threshold: Option<u8>,
+
+    /// Only detect exact renames when comparing the contents of every (deleted, added) pair would exceed this many pairs squared, like git's `diff.renameLimit`.
+    /// `None` uses git's default of 1000, `Some(0)` means no limit.
+    pub rename_limit: Option<usize>,
+}
+
+/// A single changed file (Blob or submodule) between two [Tree]s, as reported by [RepositoryCache::diff_trees]
+#[derive(Clone, Debug)]
+pub enum TreeChange {
+    /// `path` was added
+    Added {
+        /// The full path of the new entry
+        path:   Name,
+        /// The new entry
+        new:    tree::Entry,
+    },
+
+    /// `path` was deleted
+    Deleted {
+        /// The full path of the old entry
+        path:   Name,
+        /// The old entry
+        old:    tree::Entry,
+    },
+
+    /// `path`'s contents changed (and possibly it's mode, e.g. regular to executable)
+    Modified {
+        /// The full path of the entry
+        path:   Name,
+        /// The old entry
+        old:    tree::Entry,
+        /// The new entry
+        new:    tree::Entry,
+    },
+
+    /// `path`'s [Mode](tree::Mode) changed (e.g. regular to executable), but not it's contents
+    ModeChanged {
+        /// The full path of the entry
+        path:   Name,
+        /// The old entry
+        old:    tree::Entry,
+        /// The new entry
+        new:    tree::Entry,
+    },
+
+    /// `path` changed between a regular file, symlink, or submodule
+    TypeChanged {
+        /// The full path of the entry
+        path:   Name,
+        /// The old entry
+        old:    tree::Entry,
+        /// The new entry
+        new:    tree::Entry,
+    },
+
+    /// A file was moved from `old_path` to `new_path`, possibly with modifications
+    Renamed {
+        /// The full path of the old entry
+        old_path:   Name,
+        /// The full path of the new entry
+        new_path:   Name,
+        /// The old entry
+        old:        tree::Entry,
+        /// The new entry
+        new:        tree::Entry,
+        /// How similar the old and new contents are (0 ..= 100 percent)
+        similarity: u8,
+    },
+}
+
+impl TreeChange {
+    /// The (new) full path of the changed entry
+    pub fn path(&self) -> &Name {
+        match self {
+            TreeChange::Added       { path, .. }        => path,
+            TreeChange::Deleted     { path, .. }        => path,
+            TreeChange::Modified    { path, .. }        => path,
+            TreeChange::ModeChanged { path, .. }        => path,
+            TreeChange::TypeChanged { path, .. }        => path,
+            TreeChange::Renamed     { new_path, .. }    => new_path,
+        }
+    }
+}
+
+
+
+pub(crate) fn diff_trees(cache: &RepositoryCache, old: Option<&tree::Hash>, new: Option<&tree::Hash>, options: TreeDiffOptions) -> io::Result<Vec<TreeChange>> {
+    let old = match old { Some(old) => Some(cache.tree(old)?), None => None };
+    let new = match new { Some(new) => Some(cache.tree(new)?), None => None };
+    let mut changes = Vec::new();
+    diff_recursive(cache, None, old, new, &mut changes)?;
+    if let Some(threshold) = options.rename_threshold {
+        detect_renames(cache, threshold.min(100), options.rename_limit.unwrap_or(1000), &mut changes)?;
+    }
+    changes.sort_by(|l, r| l.path().as_ref().cmp(r.path().as_ref())); // recursion visits "foo/bar" before "foo.rs"
+    Ok(changes)
+}
+
+/// Where `path` in `new` was renamed from, if it was: like [diff_trees], but only considering `path` as a rename destination (`git log --follow`)
+pub(crate) fn rename_source(cache: &RepositoryCache, old: &tree::Hash, new: &tree::Hash, path: &[u8], threshold: u8) -> io::Result<Option<Name>> {
+    let mut changes = Vec::new();
+    diff_recursive(cache, None, Some(cache.tree(old)?), Some(cache.tree(new)?), &mut changes)?;
+    changes.retain(|c| match c { TreeChange::Added { path: added, .. } => added == path, _ => true });
+    detect_renames(cache, threshold.min(100), 1000, &mut changes)?;
+    Ok(changes.into_iter().find_map(|c| match c { TreeChange::Renamed { old_path, new_path, .. } if new_path == path => Some(old_path), _ => None }))
+}
+
+fn diff_recursive(cache: &RepositoryCache, dir: Option<&Name>, old: Option<Arc<Tree>>, new: Option<Arc<Tree>>, changes: &mut Vec<TreeChange>) -> io::Result<()> {
+    let empty = BTreeMap::new();
+    let old_entries = old.as_ref().map_or(&empty, |t| &t.entries);
+    let new_entries = new.as_ref().map_or(&empty, |t| &t.entries);
+
+    let names : BTreeSet<&Name> = old_entries.keys().chain(new_entries.keys()).collect();
+    for name in names {
+        let path = join_path(dir, name);
+        match (old_entries.get(name), new_entries.get(name)) {
+            (None, None) => {},
+            (Some(old), Some(new)) if old.hash == new.hash && old.mode == new.mode => {}, // identical (sub)tree or file
+            (Some(old), Some(new)) if old.mode.is_tree() && new.mode.is_tree() => {
+                diff_recursive(cache, Some(&path), Some(cache.tree(&old.hash.cast())?), Some(cache.tree(&new.hash.cast())?), changes)?;
+            },
+            (Some(old), Some(new)) if old.mode.is_tree() || new.mode.is_tree() => {
+                deleted(cache, path.clone(), old, changes)?;
+                added(cache, path, new, changes)?;
+            },
+            (Some(old), Some(new)) => {
+                let (old, new) = (old.clone(), new.clone());
+                changes.push(if kind(old.mode) != kind(new.mode) {
+                    TreeChange::TypeChanged { path, old, new }
+                } else if old.hash == new.hash {
+                    TreeChange::ModeChanged { path, old, new }
+                } else {
+                    TreeChange::Modified { path, old, new }
+                });
+            },
+            (Some(old), None)   => deleted(cache, path, old, changes)?,
+            (None, Some(new))   => added(cache, path, new, changes)?,
+        }
+    }
+    Ok(())
+}
+
+fn added(cache: &RepositoryCache, path: Name, new: &tree::Entry, changes: &mut Vec<TreeChange>) -> io::Result<()> {
+    match new.tree_hash() {
+        Some(tree)  => diff_recursive(cache, Some(&path), None, Some(cache.tree(&tree)?), changes),
+        None        => { changes.push(TreeChange::Added { path, new: new.clone() }); Ok(()) },
+    }
+}
+
+fn deleted(cache: &RepositoryCache, path: Name, old: &tree::Entry, changes: &mut Vec<TreeChange>) -> io::Result<()> {
+    match old.tree_hash() {
+        Some(tree)  => diff_recursive(cache, Some(&path), Some(cache.tree(&tree)?), None, changes),
+        None        => { changes.push(TreeChange::Deleted { path, old: old.clone() }); Ok(()) },
+    }
+}
+
+/// Regular files and executables are the same "kind" of entry - symlinks and submodules are not.
+fn kind(mode: tree::Mode) -> u32 {
+    match mode {
+        tree::Mode::Executable => tree::Mode::Regular.as_raw() & 0o170000,
+        other => other.as_raw() & 0o170000,
+    }
+}
+
+
+
+fn detect_renames(cache: &RepositoryCache, threshold: u8, limit: usize, changes: &mut Vec<TreeChange>) -> io::Result<()> {
+    let is_file = |e: &tree::Entry| e.mode.is_blob();
+    let deleted = changes.iter().enumerate().filter_map(|(i, c)| match c { TreeChange::Deleted { old, .. } if is_file(old) => Some(i), _ => None }).collect::<Vec<_>>();
+    let added   = changes.iter().enumerate().filter_map(|(i, c)| match c { TreeChange::Added   { new, .. } if is_file(new) => Some(i), _ => None }).collect::<Vec<_>>();
+    if deleted.is_empty() || added.is_empty() { return Ok(()); }
+    let old_entry = |d: usize| match &changes[d] { TreeChange::Deleted { old, .. } => old, _ => unreachable!() };
+    let new_entry = |a: usize| match &changes[a] { TreeChange::Added   { new, .. } => new, _ => unreachable!() };
+
+    // Exact renames first: cheap, and they always win
+    let mut renames = Vec::new(); // (similarity, deleted index, added index)
+    let mut by_hash = HashMap::<&unknown::Hash, Vec<usize>>::new();
+    for &d in deleted.iter().rev() { by_hash.entry(&old_entry(d).hash).or_default().push(d); }
+    let mut paired = HashSet::new();
+    for &a in added.iter() {
+        if let Some(d) = by_hash.get_mut(&new_entry(a).hash).and_then(|ds| ds.pop()) {
+            paired.insert(d);
+            paired.insert(a);
+            renames.push((100, d, a));
+        }
+    }
+
+    // Then score every remaining (deleted, added) pair, best first, unless that's too many pairs to be worth it
+    let deleted = deleted.into_iter().filter(|d| !paired.contains(d)).collect::<Vec<_>>();
+    let added   = added  .into_iter().filter(|a| !paired.contains(a)).collect::<Vec<_>>();
+    let too_many = limit != 0 && deleted.len().saturating_mul(added.len()) > limit.saturating_mul(limit);
+    if threshold < 100 && !too_many && !deleted.is_empty() && !added.is_empty() {
+        let mut contents = HashMap::<unknown::Hash, Arc<[u8]>>::new();
+        let mut read = |hash: &unknown::Hash| -> io::Result<Arc<[u8]>> {
+            if let Some(data) = contents.get(hash) { return Ok(data.clone()); }
+            let mut data = Vec::new();
+            cache.repository.cat_file_blob(&hash.cast())?.read_to_end(&mut data)?;
+            let data = Arc::<[u8]>::from(data);
+            contents.insert(hash.clone(), data.clone());
+            Ok(data)
+        };
+
+        let mut candidates = Vec::new();
+        for &d in deleted.iter() {
+            let old = read(&old_entry(d).hash)?;
+            for &a in added.iter() {
+                let score = similarity(&old, &read(&new_entry(a).hash)?);
+                if score >= threshold { candidates.push((score, d, a)); }
+            }
+        }
+        candidates.sort_by(|l, r| r.0.cmp(&l.0).then(l.1.cmp(&r.1)).then(l.2.cmp(&r.2)));
+
+        for (score, d, a) in candidates {
+            if paired.contains(&d) || paired.contains(&a) { continue; }
+            paired.insert(d);
+            paired.insert(a);
+            renames.push((score, d, a));
+        }
+    }
+
+    let mut renamed_from = HashSet::new();
+    for (similarity, d, a) in renames {
+        let (old_path, old) = match &changes[d] { TreeChange::Deleted { path, old } => (path.clone(), old.clone()), _ => unreachable!() };
+        let (new_path, new) = match &changes[a] { TreeChange::Added   { path, new } => (path.clone(), new.clone()), _ => unreachable!() };
+        changes[a] = TreeChange::Renamed { old_path, new_path, old, new, similarity };
+        renamed_from.insert(d);
+    }
+
+    let mut i = 0;
+    changes.retain(|_| { i += 1; !renamed_from.contains(&(i-1)) });
+    Ok(())
+}
+
+/// Percentage of lines shared between `a` and `b`
+fn similarity(a: &[u8], b: &[u8]) -> u8 {
+    if a.is_empty() && b.is_empty() { return 100; }
+    let mut lines = HashMap::<&[u8], isize>::new();
+    let (mut na, mut nb, mut common) = (0, 0, 0);
+    for line in split_lines(a) { *lines.entry(line).or_insert(0) += 1; na += 1; }
+    for line in split_lines(b) {
+        nb += 1;
+        if let Some(n) = lines.get_mut(line) {
+            if *n > 0 { *n -= 1; common += 1; }
+        }
+    }
+    if na + nb == 0 { return 100; }
+    (common * 200 / (na + nb)) as u8
+}
+
+/// Split on '\n', without a trailing empty line for newline terminated data
+fn split_lines(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let data = if data.last() == Some(&b'\n') { &data[..data.len()-1] } else { data };
+    data.split(|b| *b == b'\n').filter(move |_| !data.is_empty())
+}
@@ -0,0 +1,229 @@
+use crate::*;
+
+use std::io;
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Condvar, Mutex};
+
+
+
+/// The order in which a [TreeWalk] yields [Tree]s relative to their contents
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WalkOrder {
+    /// Yield a subtree's [Entry](tree::Entry) before it's contents
+    PreOrder,
+
+    /// Yield a subtree's [Entry](tree::Entry) after it's contents
+    PostOrder,
+}
+
+/// A recursive, depth first iterator over the (full path, [Entry](tree::Entry)) pairs of a [Tree], created by [RepositoryCache::walk_tree].
+///
+/// Entries are yielded in name order.  Submodules are yielded, but not descended into.
+pub struct TreeWalk<'c> {
+    cache:      &'c RepositoryCache,
+    order:      WalkOrder,
+    stack:      Vec<Frame>,
+    pending:    Option<(Name, tree::Entry)>,
+    prune:      Option<Prune<'c>>,
+}
+
+type Prune<'c> = Box<dyn FnMut(&Name, &tree::Entry) -> bool + 'c>;
+
+struct Frame {
+    /// The directory this frame lists, and it's full path (`None` for the root)
+    dir:        Option<(Name, tree::Entry)>,
+    entries:    std::vec::IntoIter<tree::Entry>,
+}
+
+impl<'c> TreeWalk<'c> {
+    pub(crate) fn new(cache: &'c RepositoryCache, tree: &tree::Hash, order: WalkOrder) -> io::Result<Self> {
+        let root = cache.tree(tree)?;
+        Ok(Self {
+            cache,
+            order,
+            stack:      vec![Frame { dir: None, entries: root.entries.values().cloned().collect::<Vec<_>>().into_iter() }],
+            pending:    None,
+            prune:      None,
+        })
+    }
+
+    /// Only descend into subtrees for which `descend(path, entry)` returns `true`.  Pruned subtrees are still yielded themselves.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let tree    = cache.repository.rev_parse_tree("HEAD^{tree}").unwrap();
+    /// for e in cache.walk_tree(&tree, WalkOrder::PostOrder).unwrap().prune(|path, _| path != "src") {
+    ///     let (path, _entry) = e.unwrap();
+    ///     assert!(!path.as_str_lossy().starts_with("src/"));
+    /// }
+    /// ```
+    pub fn prune(mut self, descend: impl FnMut(&Name, &tree::Entry) -> bool + 'c) -> Self {
+        self.prune = Some(Box::new(descend));
+        self
+    }
+
+    /// Don't descend into the subtree most recently yielded.  Only meaningful for [WalkOrder::PreOrder] walks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use clgit::*;
+    /// let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+    /// let tree    = cache.repository.rev_parse_tree("HEAD^{tree}").unwrap();
+    /// let mut walk = cache.walk_tree(&tree, WalkOrder::PreOrder).unwrap();
+    /// while let Some(e) = walk.next() {
+    ///     let (path, entry) = e.unwrap();
+    ///     if entry.mode.is_tree() && path == "examples" { walk.skip_subtree(); }
+    ///     assert!(!path.as_str_lossy().starts_with("examples/"));
+    /// }
+    /// ```
+    pub fn skip_subtree(&mut self) {
+        self.pending = None;
+    }
+
+    fn descend(&mut self, path: Name, entry: tree::Entry) -> io::Result<()> {
+        let hash = entry.tree_hash().expect("descend only called on subtrees");
+        let tree = self.cache.tree(&hash)?;
+        self.stack.push(Frame { dir: Some((path, entry)), entries: tree.entries.values().cloned().collect::<Vec<_>>().into_iter() });
+        Ok(())
+    }
+}
+
+impl Iterator for TreeWalk<'_> {
+    type Item = io::Result<(Name, tree::Entry)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((path, entry)) = self.pending.take() {
+            if let Err(e) = self.descend(path, entry) { return Some(Err(e)); }
+        }
+
+        loop {
+            let frame = self.stack.last_mut()?;
+            let entry = match frame.entries.next() {
+                Some(entry) => entry,
+                None => {
+                    let frame = self.stack.pop().unwrap();
+                    match (self.order, frame.dir) {
+                        (WalkOrder::PostOrder, Some(dir))   => return Some(Ok(dir)),
+                        _                                   => continue,
+                    }
+                },
+            };
+
+            let path = join_path(frame.dir.as_ref().map(|(path, _)| path), &entry.name);
+            let descend = entry.mode.is_tree() && match self.prune.as_mut() {
+                Some(descend)   => descend(&path, &entry),
+                None            => true,
+            };
+
+            match (self.order, descend) {
+                (_, false)                  => return Some(Ok((path, entry))),
+                (WalkOrder::PreOrder, true) => {
+                    self.pending = Some((path.clone(), entry.clone()));
+                    return Some(Ok((path, entry)));
+                },
+                (WalkOrder::PostOrder, true) => {
+                    if let Err(e) = self.descend(path, entry) { return Some(Err(e)); }
+                },
+            }
+        }
+    }
+}
+
+
+
+/// Visit every (full path, [Entry](tree::Entry)) of `tree` in unspecified order, fanning out across `threads` scoped threads.
+///
+/// `visit` returns whether or not to descend into the given entry, and is ignored for anything but subtrees.
+/// Submodules are never descended into.  The first error encountered stops the walk and is returned.
+pub(crate) fn par_walk_tree(
+    cache:      &RepositoryCache,
+    tree:       &tree::Hash,
+    threads:    usize,
+    visit:      impl Fn(&Name, &tree::Entry) -> bool + Sync,
+) -> io::Result<()> {
+    struct State {
+        queue:  Vec<(Option<Name>, tree::Hash)>,
+        active: usize,
+        error:  Option<io::Error>,
+        panic:  Option<Box<dyn Any + Send>>,
+    }
+
+    let state   = Mutex::new(State { queue: vec![(None, tree.clone())], active: 0, error: None, panic: None });
+    let cvar    = Condvar::new();
+    let worker  = || loop {
+        let (dir, hash) = {
+            let mut s = state.lock().unwrap();
+            loop {
+                if s.error.is_some() || s.panic.is_some() { return; }
+                if let Some(job) = s.queue.pop() { s.active += 1; break job; }
+                if s.active == 0 { return; }
+                s = cvar.wait(s).unwrap();
+            }
+        };
+
+        let mut subtrees = Vec::new();
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| cache.tree(&hash).map(|tree| {
+            for entry in tree.entries.values() {
+                let path = join_path(dir.as_ref(), &entry.name);
+                if visit(&path, entry) {
+                    if let Some(subtree) = entry.tree_hash() { subtrees.push((Some(path), subtree)); }
+                }
+            }
+        })));
+
+        let mut s = state.lock().unwrap();
+        s.active -= 1;
+        match result {
+            Ok(Ok(()))      => s.queue.extend(subtrees),
+            Ok(Err(e))      => if s.error.is_none() { s.error = Some(e); },
+            Err(payload)    => if s.panic.is_none() { s.panic = Some(payload); },
+        }
+        cvar.notify_all();
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 0 .. threads.max(1) { scope.spawn(worker); } // visitor panics are caught so the other workers can stop, and rethrown below
+    });
+
+    let state = state.into_inner().unwrap();
+    if let Some(payload) = state.panic { std::panic::resume_unwind(payload); }
+    match state.error {
+        Some(e) => Err(e),
+        None    => Ok(()),
+    }
+}
+
+
+
+/// Join a directory path (`None` for the root) and an entry name with a '/'
+pub(crate) fn join_path(dir: Option<&Name>, name: &Name) -> Name {
+    match dir {
+        None        => name.clone(),
+        Some(dir)   => {
+            let mut path = dir.as_bytes().map(|b| b.to_vec()).unwrap_or_else(|| dir.as_str_lossy().as_bytes().to_vec());
+            path.push(b'/');
+            path.extend_from_slice(name.as_bytes().unwrap_or_else(|| name.as_str_lossy().as_bytes()));
+            Name::from(path)
+        },
+    }
+}
+
+
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    #[test] fn par_walk_tree_resumes_panics() {
+        let cache   = RepositoryCache::new(Repository::from_path(".").unwrap());
+        let tree    = cache.repository.rev_parse_tree("HEAD^{tree}").unwrap();
+        let panic   = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let _ = par_walk_tree(&cache, &tree, 4, |_path, _entry| std::panic::panic_any(42_u32));
+        })).unwrap_err();
+        assert_eq!(panic.downcast_ref::<u32>(), Some(&42));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::*;
+
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+
+
+
+/// A work tree attached to a repository (the main one, or one added by `git worktree add`), as listed by [Repository::worktrees]
+#[derive(Clone, Debug)]
+pub struct Worktree {
+    /// The worktree's name (it's directory under `.git/worktrees/`), or `None` for the main worktree
+    pub name:       Option<OsString>,
+
+    /// The root of the checkout, or `None` for the main worktree of a bare repository.
+    ///
+    /// Also `None` if the main worktree's checkout can't be found: when it's git directory isn't named ".git" (e.g. `git init --separate-git-dir`),
+    /// `core.worktree` isn't set, and the worktrees weren't listed from the main worktree itself.
+    pub path:       Option<PathBuf>,
+
+    /// The worktree's own git directory, holding it's `HEAD` and index (e.g. ".git/worktrees/foo")
+    pub git_dir:    PathBuf,
+
+    /// Where the worktree's `HEAD` points, if readable
+    pub head:       Option<Head>,
+
+    /// `Some(reason)` if locked by `git worktree lock` (the reason may be empty)
+    pub locked:     Option<String>,
+
+    /// The checkout no longer exists, so `git worktree prune` would remove this worktree
+    pub prunable:   bool,
+
+    _nonexhaustive: (),
+}
+
+
+
+/// If `dot_git` is a `.git` *file* (linked worktrees, submodules), follow it's "gitdir: \<path\>" to the real git directory
+pub(crate) fn resolve_dot_git(dot_git: PathBuf) -> io::Result<PathBuf> {
+    if !dot_git.is_file() { return Ok(dot_git); }
+    let text = std::fs::read_to_string(&dot_git)?;
+    let text = text.trim_end();
+    if !text.starts_with("gitdir: ") { return Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid gitfile format: {}", dot_git.display()))); }
+    let base = dot_git.parent().unwrap_or_else(|| Path::new(""));
+    Ok(base.join(&text["gitdir: ".len()..]))
+}
+
+/// The directory shared refs, objects, config, etc. live in: `dot_git/commondir` (relative to `dot_git`) for linked worktrees, otherwise `dot_git` itself
+pub(crate) fn common_dir(dot_git: &Path) -> io::Result<PathBuf> {
+    match std::fs::read_to_string(dot_git.join("commondir")) {
+        Ok(common)                                          => canonicalize(&dot_git.join(common.trim_end_matches(&['\n', '\r'][..]))),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound   => Ok(dot_git.to_path_buf()),
+        Err(e)                                              => Err(e),
+    }
+}
+
+/// Is `name` (e.g. "HEAD", "refs/bisect/bad") stored in each worktree's git directory, rather than the common directory?
+pub(crate) fn is_per_worktree_ref(name: &str) -> bool {
+    !name.starts_with("refs/") || PER_WORKTREE_REF_DIRS.iter().any(|dir| name.starts_with(dir))
+}
+
+/// Ref directories stored in each worktree's git directory
+pub(crate) const PER_WORKTREE_REF_DIRS : &[&str] = &["refs/bisect/", "refs/rewritten/", "refs/worktree/"];
+
+/// List the main worktree and every linked worktree registered in `common_dir/worktrees/`
+pub(crate) fn list_worktrees(repository: &Repository) -> io::Result<Vec<Worktree>> {
+    let common_dir = repository.common_dir();
+    let read_head = |git_dir: &Path| Repository::from_dot_git(git_dir.to_path_buf(), None).and_then(|r| r.head()).ok();
+
+    let main_path = if repository.git_dir() == common_dir && repository.work_tree().is_some() {
+        repository.work_tree().map(Path::to_path_buf) // listing from the main worktree itself
+    } else if repository.config_get("core.bare", Some("bool"))?.as_deref() == Some("true") {
+        None
+    } else if let Some(work_tree) = repository.config_get("core.worktree", Some("path"))? {
+        Some(common_dir.join(work_tree)) // relative to the git directory
+    } else if common_dir.file_name() == Some(OsStr::new(".git")) {
+        common_dir.parent().map(Path::to_path_buf)
+    } else {
+        None
+    };
+    let mut worktrees = vec![Worktree {
+        name:           None,
+        path:           main_path,
+        git_dir:        common_dir.to_path_buf(),
+        head:           read_head(common_dir),
+        locked:         None,
+        prunable:       false,
+        _nonexhaustive: (),
+    }];
+
+    let dir = match common_dir.join("worktrees").read_dir() {
+        Ok(dir) => dir,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(worktrees),
+        Err(e) => return Err(e),
+    };
+    let mut linked = Vec::new();
+    for e in dir {
+        let e = e?;
+        if !e.file_type()?.is_dir() { continue; }
+        let git_dir = e.path();
+        let dot_git = std::fs::read_to_string(git_dir.join("gitdir")).ok().map(|gd| PathBuf::from(gd.trim_end_matches(&['\n', '\r'][..])));
+        let path = dot_git.as_ref().and_then(|gd| gd.parent()).map(Path::to_path_buf);
+        let locked = match std::fs::read_to_string(git_dir.join("locked")) {
+            Ok(reason)                                          => Some(reason.trim_end().to_string()),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound   => None,
+            Err(e)                                              => return Err(e),
+        };
+        linked.push(Worktree {
+            name:           Some(e.file_name()),
+            prunable:       !matches!(&dot_git, Some(gd) if gd.exists()),
+            path,
+            head:           read_head(&git_dir),
+            git_dir,
+            locked,
+            _nonexhaustive: (),
+        });
+    }
+    linked.sort_by(|a, b| a.name.cmp(&b.name));
+    worktrees.extend(linked);
+    Ok(worktrees)
+}